@@ -0,0 +1,566 @@
+use crate::atlas::SpriteAtlas;
+use ggez::glam::Vec2;
+use ggez::graphics::{Canvas, DrawParam, Image, Rect};
+use ggez::{Context, GameError, GameResult};
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::io::Read;
+
+// A single image of an animation, shown for `duration` seconds.
+// Frames can stack several sprites, drawn bottom to top (e.g. a ghost body and its eyes).
+#[derive(Debug, Clone, PartialEq)]
+pub struct Frame {
+    pub layers: Vec<Rect>,     // Atlas UV rects, ready for `DrawParam::src`.
+    pub duration: f32,         // Seconds this frame stays on screen.
+    pub event: Option<String>, // Reported by `Animator::update` when this frame comes up.
+}
+
+// A sequence of frames that either loops or holds its last frame when done.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Clip {
+    pub frames: Vec<Frame>,
+    pub looping: bool,
+}
+
+impl Clip {
+    pub fn new(frames: Vec<Frame>, looping: bool) -> Clip {
+        Clip { frames, looping }
+    }
+
+    // A clip of single-sprite frames that all last `frame_duration` seconds.
+    pub fn uniform(rects: &[Rect], frame_duration: f32, looping: bool) -> Clip {
+        let frames = rects
+            .iter()
+            .map(|&rect| Frame {
+                layers: vec![rect],
+                duration: frame_duration,
+                event: None,
+            })
+            .collect();
+        Clip::new(frames, looping)
+    }
+
+    // Length of one pass through the clip in seconds.
+    pub fn duration(&self) -> f32 {
+        self.frames.iter().map(|f| f.duration).sum()
+    }
+}
+
+// Plays one of an actor's clips, selected by a state name such as "north" or "frightened".
+// Switching state with `set_state` keeps the frame position, so an actor turning a corner
+// continues its animation in phase; `play` starts the new clip from its first frame.
+#[derive(Debug, Clone)]
+pub struct Animator {
+    clips: HashMap<String, Clip>,
+    state: String,
+    frame: usize,    // Index into the current clip's frames.
+    frame_time: f32, // Seconds the current frame has been shown.
+    paused: bool,    // While paused `update` doesn't advance, freezing the current frame.
+    finished: bool,  // A non-looping clip reached the end of its last frame.
+    started: bool,   // `update` ran since the clip (re)started and reported its first frame.
+}
+
+impl Animator {
+    // Creates an animator showing the clip for `state`, which should be one of `clips`.
+    pub fn new(clips: HashMap<String, Clip>, state: &str) -> Animator {
+        Animator {
+            clips,
+            state: state.to_string(),
+            frame: 0,
+            frame_time: 0.0,
+            paused: false,
+            finished: false,
+            started: false,
+        }
+    }
+
+    pub fn state(&self) -> &str {
+        &self.state
+    }
+
+    pub fn has_state(&self, state: &str) -> bool {
+        self.clips.contains_key(state)
+    }
+
+    // Switches to the clip for `state`, keeping the current frame position.
+    pub fn set_state(&mut self, state: &str) {
+        if self.state != state {
+            self.state = state.to_string();
+            let len = self.clip().map_or(0, |clip| clip.frames.len());
+            self.frame = if len == 0 { 0 } else { self.frame % len };
+            self.finished = false;
+        }
+    }
+
+    // Switches to the clip for `state` and starts it over, unless it is already playing.
+    pub fn play(&mut self, state: &str) {
+        if self.state != state {
+            self.state = state.to_string();
+            self.restart();
+        }
+    }
+
+    // Starts the current clip over from its first frame.
+    pub fn restart(&mut self) {
+        self.frame = 0;
+        self.frame_time = 0.0;
+        self.finished = false;
+        self.started = false;
+    }
+
+    pub fn pause(&mut self) {
+        self.paused = true;
+    }
+
+    pub fn resume(&mut self) {
+        self.paused = false;
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.paused
+    }
+
+    // True once a non-looping clip has shown all of its frames.
+    pub fn is_finished(&self) -> bool {
+        self.finished
+    }
+
+    // Advances the animation by `dt` seconds unless paused, returning the events of
+    // every frame that came up on the way (several if `dt` skipped over frames). The first
+    // update after the clip (re)started also reports the first frame's event.
+    pub fn update(&mut self, dt: f32) -> Vec<String> {
+        let mut events = Vec::new();
+        if self.paused || self.finished {
+            return events;
+        }
+        let Some(clip) = self.clips.get(&self.state) else {
+            return events;
+        };
+        // A clip without any frame time would never advance; just show its first frame.
+        if clip.frames.is_empty() || clip.duration() <= 0.0 {
+            return events;
+        }
+
+        if !self.started {
+            self.started = true;
+            if let Some(event) = &clip.frames[self.frame].event {
+                events.push(event.clone());
+            }
+        }
+        self.frame_time += dt;
+        while self.frame_time >= clip.frames[self.frame].duration {
+            if self.frame + 1 == clip.frames.len() && !clip.looping {
+                self.frame_time = clip.frames[self.frame].duration;
+                self.finished = true;
+                break;
+            }
+            self.frame_time -= clip.frames[self.frame].duration;
+            self.frame = (self.frame + 1) % clip.frames.len();
+            if let Some(event) = &clip.frames[self.frame].event {
+                events.push(event.clone());
+            }
+        }
+        events
+    }
+
+    // The frame to draw right now, or `None` if the current state has no clip.
+    pub fn frame(&self) -> Option<&Frame> {
+        self.clip()?.frames.get(self.frame)
+    }
+
+    // Draws every layer of the current frame from `image` with its top-left corner at `dest`.
+    pub fn draw(&self, canvas: &mut Canvas, image: &Image, dest: Vec2) {
+        if let Some(frame) = self.frame() {
+            for &layer in &frame.layers {
+                canvas.draw(image, DrawParam::new().dest(dest).src(layer));
+            }
+        }
+    }
+
+    fn clip(&self) -> Option<&Clip> {
+        self.clips.get(&self.state)
+    }
+}
+
+// The animation file, e.g. `resources/animations.json`:
+//
+//     {
+//       "ghost": {
+//         "clips": { "eyes_north": { "frames": ["ghost_eyes_north"] } }
+//       },
+//       "blinky": {
+//         "extends": "ghost",
+//         "clips": {
+//           "north": {
+//             "frames": [["blinky_0", "ghost_eyes_north"], ["blinky_1", "ghost_eyes_north"]],
+//             "frame_duration": 0.13
+//           }
+//         }
+//       }
+//     }
+//
+// Each actor has named clips and inherits the clips of the actor named by `extends`.
+// A frame is a sprite name, a list of sprite names drawn on top of each other, or an
+// object `{ "sprites": ..., "duration": 0.2, "event": "step" }` that overrides the clip's
+// `frame_duration` (default 0.1s) and names an event. Clips loop unless `"looping": false`.
+// Each actor's `extends` chain has to end in an actor without one.
+#[derive(Debug, Clone, Deserialize)]
+pub struct ActorDef {
+    #[serde(default)]
+    pub extends: Option<String>,
+    pub clips: HashMap<String, ClipDef>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct ClipDef {
+    pub frames: Vec<FrameDef>,
+    #[serde(default = "default_frame_duration")]
+    pub frame_duration: f32,
+    #[serde(default = "default_looping")]
+    pub looping: bool,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(untagged)]
+pub enum FrameDef {
+    Sprites(SpriteList),
+    Detailed {
+        sprites: SpriteList,
+        #[serde(default)]
+        duration: Option<f32>,
+        #[serde(default)]
+        event: Option<String>,
+    },
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(untagged)]
+pub enum SpriteList {
+    One(String),
+    Layers(Vec<String>),
+}
+
+impl SpriteList {
+    pub fn names(&self) -> &[String] {
+        match self {
+            SpriteList::One(name) => std::slice::from_ref(name),
+            SpriteList::Layers(names) => names,
+        }
+    }
+}
+
+fn default_frame_duration() -> f32 {
+    0.1
+}
+
+fn default_looping() -> bool {
+    true
+}
+
+// Every actor's clips from an animation file, with sprite names resolved to atlas rects.
+#[derive(Debug, Clone, Default)]
+pub struct AnimationLibrary {
+    actors: HashMap<String, (Option<String>, HashMap<String, Clip>)>, // Parent and own clips.
+}
+
+// Parses an animation file without touching the filesystem. `sprite` resolves sprite
+// names to atlas rects, normally through `SpriteAtlas::get`.
+pub fn parse_animation_library(
+    content: &str,
+    sprite: impl Fn(&str) -> Option<Rect>,
+) -> Result<AnimationLibrary, String> {
+    let defs: HashMap<String, ActorDef> =
+        serde_json::from_str(content).map_err(|e| e.to_string())?;
+
+    let mut actors = HashMap::new();
+    for (actor, def) in defs {
+        let mut clips = HashMap::new();
+        for (name, clip_def) in def.clips {
+            let mut frames = Vec::with_capacity(clip_def.frames.len());
+            for frame_def in &clip_def.frames {
+                let (sprites, duration, event) = match frame_def {
+                    FrameDef::Sprites(sprites) => (sprites, None, None),
+                    FrameDef::Detailed {
+                        sprites,
+                        duration,
+                        event,
+                    } => (sprites, *duration, event.clone()),
+                };
+                let layers = sprites
+                    .names()
+                    .iter()
+                    .map(|sprite_name| {
+                        sprite(sprite_name).ok_or_else(|| {
+                            format!("{}/{}: unknown sprite {:?}", actor, name, sprite_name)
+                        })
+                    })
+                    .collect::<Result<Vec<_>, _>>()?;
+                frames.push(Frame {
+                    layers,
+                    duration: duration.unwrap_or(clip_def.frame_duration),
+                    event,
+                });
+            }
+            clips.insert(name, Clip::new(frames, clip_def.looping));
+        }
+        actors.insert(actor, (def.extends, clips));
+    }
+
+    for (actor, (extends, _)) in &actors {
+        let mut seen = vec![actor];
+        let mut parent = extends.as_ref();
+        while let Some(name) = parent {
+            let Some((grandparent, _)) = actors.get(name) else {
+                return Err(format!("{} extends unknown actor {:?}", actor, name));
+            };
+            if seen.contains(&name) {
+                return Err(format!("{} extends itself through {:?}", actor, name));
+            }
+            seen.push(name);
+            parent = grandparent.as_ref();
+        }
+    }
+    Ok(AnimationLibrary { actors })
+}
+
+impl AnimationLibrary {
+    // Loads an animation file such as "/animations.json", resolving sprites through `atlas`.
+    pub fn load(
+        ctx: &mut Context,
+        path: &str,
+        atlas: &SpriteAtlas,
+    ) -> GameResult<AnimationLibrary> {
+        let mut content = String::new();
+        ctx.fs.open(path)?.read_to_string(&mut content)?;
+        parse_animation_library(&content, |name| atlas.get(name)).map_err(|e| {
+            GameError::ResourceLoadError(format!("Invalid animation file {}: {}", path, e))
+        })
+    }
+
+    // All clips of an actor including inherited ones, the actor's own clips taking precedence.
+    pub fn clips(&self, actor: &str) -> Option<HashMap<String, Clip>> {
+        let (extends, own) = self.actors.get(actor)?;
+        // `parse_animation_library` made sure every chain ends.
+        let mut chain = vec![own];
+        let mut parent = extends.as_deref();
+        while let Some(name) = parent {
+            let (grandparent, clips) = self.actors.get(name)?;
+            chain.push(clips);
+            parent = grandparent.as_deref();
+        }
+        let mut merged = HashMap::new();
+        for clips in chain.into_iter().rev() {
+            merged.extend(clips.iter().map(|(k, v)| (k.clone(), v.clone())));
+        }
+        Some(merged)
+    }
+
+    // Creates an animator for an actor starting in `state`. Both have to exist in the file.
+    pub fn animator(&self, actor: &str, state: &str) -> GameResult<Animator> {
+        let clips = self.clips(actor).ok_or_else(|| {
+            GameError::ResourceLoadError(format!("Actor {:?} missing from animation file", actor))
+        })?;
+        if !clips.contains_key(state) {
+            return Err(GameError::ResourceLoadError(format!(
+                "Actor {:?} has no {:?} clip",
+                actor, state
+            )));
+        }
+        Ok(Animator::new(clips, state))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Sprites "a".."z", each resolving to its own rect.
+    fn sprite(name: &str) -> Option<Rect> {
+        let &[letter] = name.as_bytes() else {
+            return None;
+        };
+        letter
+            .is_ascii_lowercase()
+            .then(|| Rect::new((letter - b'a') as f32, 0.0, 1.0, 1.0))
+    }
+
+    fn rect(name: &str) -> Rect {
+        sprite(name).unwrap()
+    }
+
+    fn frame(name: &str, event: Option<&str>) -> Frame {
+        Frame {
+            layers: vec![rect(name)],
+            duration: 0.1,
+            event: event.map(str::to_string),
+        }
+    }
+
+    fn animator(looping: bool) -> Animator {
+        let walk = vec![frame("a", Some("step")), frame("b", None), frame("c", None)];
+        let run = vec![frame("x", None), frame("y", None), frame("z", None)];
+        let clips = HashMap::from([
+            ("walk".to_string(), Clip::new(walk, looping)),
+            ("run".to_string(), Clip::new(run, looping)),
+        ]);
+        Animator::new(clips, "walk")
+    }
+
+    fn shown(animator: &Animator) -> Rect {
+        animator.frame().unwrap().layers[0]
+    }
+
+    #[test]
+    fn clips_are_inherited_through_extends() {
+        let library = parse_animation_library(
+            r#"{
+                "base": { "clips": { "idle": { "frames": ["a"] }, "walk": { "frames": ["b"] } } },
+                "middle": { "extends": "base", "clips": { "walk": { "frames": ["c", ["d", "e"]] } } },
+                "top": { "extends": "middle", "clips": { "run": { "frames": ["f"], "looping": false } } }
+            }"#,
+            sprite,
+        )
+        .unwrap();
+
+        let clips = library.clips("top").unwrap();
+        assert_eq!(clips.len(), 3);
+        assert_eq!(clips["idle"].frames[0].layers, vec![rect("a")]);
+        assert_eq!(clips["walk"].frames[1].layers, vec![rect("d"), rect("e")]);
+        assert!(!clips["run"].looping);
+        assert_eq!(
+            library.clips("base").unwrap()["walk"].frames[0].layers,
+            vec![rect("b")]
+        );
+        assert!(library.clips("missing").is_none());
+    }
+
+    #[test]
+    fn detailed_frames_override_duration_and_name_events() {
+        let library = parse_animation_library(
+            r#"{ "actor": { "clips": { "clip": {
+                "frames": ["a", { "sprites": "b", "duration": 0.5, "event": "hit" }],
+                "frame_duration": 0.2
+            } } } }"#,
+            sprite,
+        )
+        .unwrap();
+
+        let clip = &library.clips("actor").unwrap()["clip"];
+        assert_eq!(clip.frames[0].duration, 0.2);
+        assert_eq!(clip.frames[0].event, None);
+        assert_eq!(clip.frames[1].duration, 0.5);
+        assert_eq!(clip.frames[1].event.as_deref(), Some("hit"));
+    }
+
+    #[test]
+    fn unknown_sprite_is_rejected() {
+        let error = parse_animation_library(
+            r#"{ "actor": { "clips": { "clip": { "frames": ["a", "missing"] } } } }"#,
+            sprite,
+        )
+        .unwrap_err();
+        assert_eq!(error, r#"actor/clip: unknown sprite "missing""#);
+    }
+
+    #[test]
+    fn unknown_parent_is_rejected() {
+        let error = parse_animation_library(
+            r#"{ "actor": { "extends": "nobody", "clips": {} } }"#,
+            sprite,
+        )
+        .unwrap_err();
+        assert_eq!(error, r#"actor extends unknown actor "nobody""#);
+    }
+
+    #[test]
+    fn extends_cycle_is_rejected() {
+        let error = parse_animation_library(
+            r#"{
+                "a": { "extends": "b", "clips": {} },
+                "b": { "extends": "a", "clips": {} }
+            }"#,
+            sprite,
+        )
+        .unwrap_err();
+        assert!(error.contains("extends itself through"), "{}", error);
+
+        let error = parse_animation_library(r#"{ "a": { "extends": "a", "clips": {} } }"#, sprite)
+            .unwrap_err();
+        assert_eq!(error, r#"a extends itself through "a""#);
+    }
+
+    #[test]
+    fn looping_clip_wraps_around() {
+        let mut animator = animator(true);
+        for name in ["a", "b", "c", "a", "b"] {
+            assert_eq!(shown(&animator), rect(name));
+            animator.update(0.1);
+        }
+        assert!(!animator.is_finished());
+    }
+
+    #[test]
+    fn non_looping_clip_holds_its_last_frame() {
+        let mut animator = animator(false);
+        animator.update(0.25);
+        assert_eq!(shown(&animator), rect("c"));
+        assert!(!animator.is_finished());
+
+        animator.update(0.1);
+        assert!(animator.is_finished());
+        assert!(animator.update(1.0).is_empty());
+        assert_eq!(shown(&animator), rect("c"));
+    }
+
+    #[test]
+    fn update_skips_several_frames_and_reports_their_events() {
+        let mut animator = animator(true);
+        assert_eq!(animator.update(0.05), vec!["step"]);
+        assert_eq!(shown(&animator), rect("a"));
+
+        // From halfway through "a" over "b", "c" and "a" again halfway into "b".
+        assert_eq!(animator.update(0.4), vec!["step"]);
+        assert_eq!(shown(&animator), rect("b"));
+    }
+
+    #[test]
+    fn first_frame_event_is_reported_after_restart() {
+        let mut animator = animator(false);
+        assert_eq!(animator.update(0.0), vec!["step"]);
+        assert!(animator.update(0.0).is_empty());
+
+        animator.restart();
+        assert_eq!(animator.update(0.0), vec!["step"]);
+
+        animator.play("run");
+        animator.play("walk");
+        assert_eq!(animator.update(0.0), vec!["step"]);
+    }
+
+    #[test]
+    fn set_state_keeps_the_frame_position() {
+        let mut animator = animator(true);
+        animator.update(0.15);
+        assert_eq!(shown(&animator), rect("b"));
+
+        animator.set_state("run");
+        assert_eq!(shown(&animator), rect("y"));
+        animator.update(0.05);
+        assert_eq!(shown(&animator), rect("z"));
+
+        animator.play("walk");
+        assert_eq!(shown(&animator), rect("a"));
+    }
+
+    #[test]
+    fn paused_animator_does_not_advance() {
+        let mut animator = animator(true);
+        animator.pause();
+        assert!(animator.update(1.0).is_empty());
+        assert_eq!(shown(&animator), rect("a"));
+
+        animator.resume();
+        animator.update(0.1);
+        assert_eq!(shown(&animator), rect("b"));
+    }
+}
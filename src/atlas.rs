@@ -0,0 +1,78 @@
+use ggez::graphics::{Image, Rect};
+use ggez::{Context, GameError, GameResult};
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::io::Read;
+
+// The JSON manifest describing a texture atlas, e.g. `resources/atlas.json`:
+//
+//     {
+//       "image": "/atlas.png",
+//       "wall_scheme": "cardinal16",
+//       "sprites": { "wall_0": [0, 0, 8, 8], "player_north": [32, 16, 8, 8] }
+//     }
+//
+// Sprite rects are `[x, y, width, height]` in pixels of the atlas image.
+#[derive(Debug, Clone, Deserialize)]
+pub struct AtlasManifest {
+    pub image: String, // Resource path of the atlas image.
+    #[serde(default)]
+    pub wall_scheme: Option<String>, // Autotiling scheme of the `wall_N` sprites.
+    pub sprites: HashMap<String, [u32; 4]>, // Named pixel rects.
+}
+
+// Parses an atlas manifest without touching the filesystem.
+pub fn parse_atlas_manifest(content: &str) -> Result<AtlasManifest, String> {
+    serde_json::from_str(content).map_err(|e| e.to_string())
+}
+
+// A single image holding many sprites, looked up by the names given in its manifest.
+pub struct SpriteAtlas {
+    image: Image,
+    manifest: AtlasManifest,
+}
+
+impl SpriteAtlas {
+    // Loads a manifest such as "/atlas.json" and the image it points to.
+    pub fn load(ctx: &mut Context, manifest_path: &str) -> GameResult<SpriteAtlas> {
+        let mut content = String::new();
+        ctx.fs.open(manifest_path)?.read_to_string(&mut content)?;
+        let manifest = parse_atlas_manifest(&content).map_err(|e| {
+            GameError::ResourceLoadError(format!("Invalid atlas manifest {}: {}", manifest_path, e))
+        })?;
+        let image = Image::from_path(ctx, &manifest.image)?;
+        Ok(SpriteAtlas { image, manifest })
+    }
+
+    // The atlas image every sprite is drawn from.
+    pub fn image(&self) -> &Image {
+        &self.image
+    }
+
+    pub fn manifest(&self) -> &AtlasManifest {
+        &self.manifest
+    }
+
+    pub fn contains(&self, name: &str) -> bool {
+        self.manifest.sprites.contains_key(name)
+    }
+
+    // The normalized UV rect of a sprite, ready for `DrawParam::src`, or `None` if it isn't in the manifest.
+    pub fn get(&self, name: &str) -> Option<Rect> {
+        let [x, y, w, h] = *self.manifest.sprites.get(name)?;
+        let (atlas_w, atlas_h) = (self.image.width() as f32, self.image.height() as f32);
+        Some(Rect::new(
+            x as f32 / atlas_w,
+            y as f32 / atlas_h,
+            w as f32 / atlas_w,
+            h as f32 / atlas_h,
+        ))
+    }
+
+    // Like `get`, but a missing sprite is a load error. Used for sprites the game can't run without.
+    pub fn sprite(&self, name: &str) -> GameResult<Rect> {
+        self.get(name).ok_or_else(|| {
+            GameError::ResourceLoadError(format!("Sprite {:?} missing from atlas manifest", name))
+        })
+    }
+}
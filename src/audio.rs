@@ -0,0 +1,319 @@
+use crate::events::GameEvent;
+use crate::ghost::GhostMode;
+use crate::sim::Simulation;
+use crate::synth::{self, SoundBank};
+use ggez::audio::{SoundData, SoundSource, Source};
+use ggez::{Context, GameError, GameResult};
+use std::io::Read;
+
+// Every sound the game makes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Sound {
+    Intro,
+    Waka1, // The two halves of the chomp, alternated dot by dot.
+    Waka2,
+    Siren,
+    Frightened,
+    Eyes,
+    Death,
+    Fruit,
+    ExtraLife,
+}
+
+impl Sound {
+    pub const ALL: [Sound; 9] = [
+        Sound::Intro,
+        Sound::Waka1,
+        Sound::Waka2,
+        Sound::Siren,
+        Sound::Frightened,
+        Sound::Eyes,
+        Sound::Death,
+        Sound::Fruit,
+        Sound::ExtraLife,
+    ];
+
+    // Name of the sound in the sound bank.
+    pub fn name(self) -> &'static str {
+        match self {
+            Sound::Intro => "intro",
+            Sound::Waka1 => "waka_1",
+            Sound::Waka2 => "waka_2",
+            Sound::Siren => "siren",
+            Sound::Frightened => "frightened",
+            Sound::Eyes => "eyes",
+            Sound::Death => "death",
+            Sound::Fruit => "fruit",
+            Sound::ExtraLife => "extra_life",
+        }
+    }
+
+    // The sound with the given `name`, e.g. from an animation frame's event.
+    pub fn from_name(name: &str) -> Option<Sound> {
+        Sound::ALL.into_iter().find(|sound| sound.name() == name)
+    }
+}
+
+// The siren climbs in this many steps over a level, each `SIREN_PITCH_STEP` higher than
+// the last, like the arcade's five sirens.
+pub const SIREN_STAGES: usize = 5;
+pub const SIREN_PITCH_STEP: f32 = 0.12;
+
+// Pitch of the siren with `dots_left` of a level's `dot_count` dots left.
+pub fn siren_pitch(dots_left: usize, dot_count: usize) -> f32 {
+    let eaten = dot_count.saturating_sub(dots_left);
+    let stage = (eaten * SIREN_STAGES / dot_count.max(1)).min(SIREN_STAGES - 1);
+    1.0 + stage as f32 * SIREN_PITCH_STEP
+}
+
+// Picks the sounds for what happens in the game, without playing anything.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct SoundCues {
+    second_waka: bool, // The next dot plays the second half of the chomp.
+}
+
+impl SoundCues {
+    pub fn new() -> SoundCues {
+        SoundCues::default()
+    }
+
+    // The sound to play for an event, if it has one. The death sound comes from the
+    // death animation instead, which starts after a short freeze.
+    pub fn event_sound(&mut self, event: &GameEvent) -> Option<Sound> {
+        match event {
+            GameEvent::DotEaten { .. } | GameEvent::PelletEaten { .. } => {
+                self.second_waka = !self.second_waka;
+                Some(if self.second_waka {
+                    Sound::Waka1
+                } else {
+                    Sound::Waka2
+                })
+            }
+            GameEvent::FruitEaten { .. } => Some(Sound::Fruit),
+            GameEvent::ExtraLife => Some(Sound::ExtraLife),
+            _ => None,
+        }
+    }
+
+    // The loop under the running game and its pitch: eyes heading home drown out
+    // frightened ghosts, which drown out the siren.
+    pub fn background_loop(sim: &Simulation) -> (Sound, f32) {
+        let any = |mode: fn(&GhostMode) -> bool| sim.ghosts.iter().any(|g| mode(&g.mode));
+        if any(|m| *m == GhostMode::Eyes) {
+            (Sound::Eyes, 1.0)
+        } else if any(|m| matches!(m, GhostMode::Frightened { .. })) {
+            (Sound::Frightened, 1.0)
+        } else {
+            (Sound::Siren, siren_pitch(sim.dots_left(), sim.dot_count()))
+        }
+    }
+}
+
+fn load_sound_bank(ctx: &mut Context, path: &str) -> GameResult<SoundBank> {
+    let mut content = String::new();
+    ctx.fs.open(path)?.read_to_string(&mut content)?;
+    synth::parse_sound_bank(&content)
+        .map_err(|e| GameError::ResourceLoadError(format!("Invalid sound bank {}: {}", path, e)))
+}
+
+// Change of volume per press of the volume keys.
+pub const VOLUME_STEP: f32 = 0.1;
+
+// Plays the game's sounds through ggez, rendered by the synthesizer from a sound bank such
+// as `resources/sounds.json`. Sounds that fail to load or play stay silent rather than
+// stopping the game. At most one loop plays at a time; it keeps playing only while it is
+// asked for with `keep_loop` on every tick.
+pub struct AudioPlayer {
+    sources: Vec<(Sound, Source)>,
+    missing: Vec<Sound>, // Sounds that failed to load, for telling the player once.
+    volume: f32,         // 0.0 to 1.0.
+    muted: bool,
+    current_loop: Option<(Sound, f32)>, // With its pitch.
+    loop_kept: bool,                    // `keep_loop` was called this tick.
+}
+
+impl AudioPlayer {
+    // Renders every sound in `Sound::ALL` from the sound bank at `bank_path`, e.g.
+    // "/sounds.json".
+    pub fn load(ctx: &mut Context, bank_path: &str) -> AudioPlayer {
+        // Without a bank every sound is missing.
+        let bank = load_sound_bank(ctx, bank_path).unwrap_or_default();
+        let mut sources = Vec::new();
+        let mut missing = Vec::new();
+        for sound in Sound::ALL {
+            let source = bank
+                .render(sound.name())
+                .map(|samples| SoundData::from(synth::encode_wav(&samples)))
+                .and_then(|data| Source::from_data(ctx, data).ok());
+            match source {
+                Some(source) => sources.push((sound, source)),
+                None => missing.push(sound),
+            }
+        }
+        AudioPlayer {
+            sources,
+            missing,
+            volume: 0.7,
+            muted: false,
+            current_loop: None,
+            loop_kept: false,
+        }
+    }
+
+    // The sounds that didn't load and stay silent.
+    pub fn missing(&self) -> &[Sound] {
+        &self.missing
+    }
+
+    pub fn volume(&self) -> f32 {
+        self.volume
+    }
+
+    pub fn set_volume(&mut self, volume: f32) {
+        self.volume = volume.clamp(0.0, 1.0);
+        self.apply_volume();
+    }
+
+    pub fn is_muted(&self) -> bool {
+        self.muted
+    }
+
+    pub fn toggle_mute(&mut self) {
+        self.muted = !self.muted;
+        self.apply_volume();
+    }
+
+    fn effective_volume(&self) -> f32 {
+        if self.muted { 0.0 } else { self.volume }
+    }
+
+    fn apply_volume(&mut self) {
+        let volume = self.effective_volume();
+        for (_, source) in &mut self.sources {
+            source.set_volume(volume);
+        }
+    }
+
+    fn source(&mut self, sound: Sound) -> Option<&mut Source> {
+        let volume = self.effective_volume();
+        let (_, source) = self.sources.iter_mut().find(|(s, _)| *s == sound)?;
+        source.set_volume(volume);
+        Some(source)
+    }
+
+    // Plays a sound once. Playing it again before it ends starts it over.
+    pub fn play(&mut self, ctx: &Context, sound: Sound) {
+        if let Some(source) = self.source(sound) {
+            source.set_repeat(false);
+            // A sound that can't play is skipped like a missing one.
+            let _ = source.play(ctx);
+        }
+    }
+
+    // Starts `sound` looping at `pitch`, or keeps it going if it already is, for one
+    // more tick.
+    pub fn keep_loop(&mut self, ctx: &Context, sound: Sound, pitch: f32) {
+        self.loop_kept = true;
+        if self.current_loop == Some((sound, pitch)) {
+            return;
+        }
+        self.stop_loop(ctx);
+        self.current_loop = Some((sound, pitch));
+        if let Some(source) = self.source(sound) {
+            // ggez applies the pitch when playback starts, so a new pitch means a restart.
+            source.set_repeat(true);
+            source.set_pitch(pitch);
+            let _ = source.play(ctx);
+        }
+    }
+
+    // Stops the loop unless it was kept during the tick that just ended.
+    pub fn end_tick(&mut self, ctx: &Context) {
+        if !self.loop_kept {
+            self.stop_loop(ctx);
+        }
+        self.loop_kept = false;
+    }
+
+    fn stop_loop(&mut self, ctx: &Context) {
+        let Some((sound, _)) = self.current_loop.take() else {
+            return;
+        };
+        if let Some(source) = self.source(sound) {
+            let _ = source.stop(ctx);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::load_level_from_string;
+
+    const LEVEL: &str = include_str!("../resources/levels/level1.txt");
+
+    fn stage(dots_left: usize, dot_count: usize) -> f32 {
+        (siren_pitch(dots_left, dot_count) - 1.0) / SIREN_PITCH_STEP
+    }
+
+    #[test]
+    fn siren_climbs_a_stage_every_fifth_of_the_dots() {
+        let stages: Vec<_> = [100, 81, 80, 61, 60, 40, 20, 1, 0]
+            .into_iter()
+            .map(|dots_left| stage(dots_left, 100).round() as usize)
+            .collect();
+        assert_eq!(stages, [0, 0, 1, 1, 2, 3, 4, 4, 4]);
+        assert_eq!(siren_pitch(100, 100), 1.0);
+    }
+
+    #[test]
+    fn siren_stays_low_without_dots() {
+        assert_eq!(siren_pitch(0, 0), 1.0);
+        assert_eq!(siren_pitch(5, 0), 1.0);
+    }
+
+    #[test]
+    fn eaten_dots_alternate_the_waka_halves() {
+        let mut cues = SoundCues::new();
+        let sounds: Vec<_> = [
+            GameEvent::DotEaten { tile: (1, 1) },
+            GameEvent::DotEaten { tile: (2, 1) },
+            GameEvent::PelletEaten { tile: (3, 1) },
+            GameEvent::ExtraLife,
+            GameEvent::DotEaten { tile: (4, 1) },
+        ]
+        .iter()
+        .map(|event| cues.event_sound(event))
+        .collect();
+        assert_eq!(
+            sounds,
+            [
+                Some(Sound::Waka1),
+                Some(Sound::Waka2),
+                Some(Sound::Waka1),
+                Some(Sound::ExtraLife),
+                Some(Sound::Waka2),
+            ]
+        );
+    }
+
+    #[test]
+    fn eyes_drown_out_frightened_ghosts_which_drown_out_the_siren() {
+        let mut sim = Simulation::new(load_level_from_string(LEVEL), 1);
+        assert_eq!(SoundCues::background_loop(&sim), (Sound::Siren, 1.0));
+
+        sim.ghosts[0].mode = GhostMode::Frightened { time_left: 5.0 };
+        assert_eq!(SoundCues::background_loop(&sim), (Sound::Frightened, 1.0));
+
+        sim.ghosts[1].mode = GhostMode::Eyes;
+        assert_eq!(SoundCues::background_loop(&sim), (Sound::Eyes, 1.0));
+    }
+
+    #[test]
+    fn sounds_are_found_by_name() {
+        for sound in Sound::ALL {
+            assert_eq!(Sound::from_name(sound.name()), Some(sound));
+        }
+        assert_eq!(Sound::from_name("step"), None);
+    }
+}
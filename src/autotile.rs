@@ -0,0 +1,500 @@
+use crate::{Direction, Grid, is_wall_at};
+
+// Bits of a wall mask. Each one is set when the neighbor on that side is also a wall,
+// so a mask of 0-15 selects one of the `wall_N` sprites in `resources/atlas.json` (see
+// `resources/wall_guide.md`).
+pub const WALL_NORTH: u8 = 1;
+pub const WALL_SOUTH: u8 = 2;
+pub const WALL_WEST: u8 = 4;
+pub const WALL_EAST: u8 = 8;
+
+// Diagonal bits, only used by the blob scheme. A corner bit is only kept when both
+// edges next to it are walls too, which is what collapses 256 masks down to 47 tiles.
+pub const WALL_NORTH_WEST: u8 = 16;
+pub const WALL_NORTH_EAST: u8 = 32;
+pub const WALL_SOUTH_WEST: u8 = 64;
+pub const WALL_SOUTH_EAST: u8 = 128;
+
+// Number of distinct masks in the blob scheme.
+pub const BLOB_TILE_COUNT: usize = 47;
+
+// How wall masks are computed and mapped onto tile images.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum TileScheme {
+    // 4-neighbor masks, one atlas sprite per mask (`wall_0`..`wall_15`).
+    #[default]
+    Cardinal16,
+    // 8-neighbor masks reduced to the 47 tiles of a blob tileset, so concave corners
+    // and the interior of thick walls get their own images.
+    Blob47,
+}
+
+impl TileScheme {
+    // Parses the scheme name used in tileset descriptions.
+    pub fn from_name(name: &str) -> Option<TileScheme> {
+        match name.trim() {
+            "cardinal16" => Some(TileScheme::Cardinal16),
+            "blob47" => Some(TileScheme::Blob47),
+            _ => None,
+        }
+    }
+
+    // Number of tile images a tileset of this scheme has to provide.
+    pub fn tile_count(self) -> usize {
+        match self {
+            TileScheme::Cardinal16 => 16,
+            TileScheme::Blob47 => BLOB_TILE_COUNT,
+        }
+    }
+
+    // Computes this scheme's mask for the cell at (x, y).
+    pub fn mask(self, map: &Grid<u8>, x: usize, y: usize) -> u8 {
+        match self {
+            TileScheme::Cardinal16 => wall_mask(map, x, y),
+            TileScheme::Blob47 => blob_mask(map, x, y),
+        }
+    }
+
+    // Maps a mask produced by `mask` onto the index of the tile image to draw.
+    pub fn tile_index(self, mask: u8) -> usize {
+        match self {
+            TileScheme::Cardinal16 => (mask & 15) as usize,
+            TileScheme::Blob47 => BLOB_INDEX_BY_MASK[mask as usize] as usize,
+        }
+    }
+}
+
+// Computes the 4-neighbor wall mask for a single cell. Out-of-bounds neighbors count as walls.
+pub fn wall_mask(map: &Grid<u8>, x: usize, y: usize) -> u8 {
+    let (x, y) = (x as isize, y as isize);
+    let mut mask = 0;
+    if is_wall_at(x, y - 1, map) {
+        mask |= WALL_NORTH;
+    }
+    if is_wall_at(x, y + 1, map) {
+        mask |= WALL_SOUTH;
+    }
+    if is_wall_at(x - 1, y, map) {
+        mask |= WALL_WEST;
+    }
+    if is_wall_at(x + 1, y, map) {
+        mask |= WALL_EAST;
+    }
+    mask
+}
+
+// Computes the 8-neighbor blob mask for a single cell, with corner bits dropped
+// unless both adjacent edges are set. The result is always one of `BLOB_MASKS`.
+pub fn blob_mask(map: &Grid<u8>, x: usize, y: usize) -> u8 {
+    let mut mask = wall_mask(map, x, y);
+    let (x, y) = (x as isize, y as isize);
+    let corners = [
+        (WALL_NORTH_WEST, WALL_NORTH | WALL_WEST, -1, -1),
+        (WALL_NORTH_EAST, WALL_NORTH | WALL_EAST, 1, -1),
+        (WALL_SOUTH_WEST, WALL_SOUTH | WALL_WEST, -1, 1),
+        (WALL_SOUTH_EAST, WALL_SOUTH | WALL_EAST, 1, 1),
+    ];
+    for (corner, edges, dx, dy) in corners {
+        if mask & edges == edges && is_wall_at(x + dx, y + dy, map) {
+            mask |= corner;
+        }
+    }
+    mask
+}
+
+// Returns true if a mask keeps no corner bit without both of its edges.
+const fn is_reduced_blob_mask(mask: u8) -> bool {
+    let corners = [
+        (WALL_NORTH_WEST, WALL_NORTH | WALL_WEST),
+        (WALL_NORTH_EAST, WALL_NORTH | WALL_EAST),
+        (WALL_SOUTH_WEST, WALL_SOUTH | WALL_WEST),
+        (WALL_SOUTH_EAST, WALL_SOUTH | WALL_EAST),
+    ];
+    let mut i = 0;
+    while i < corners.len() {
+        let (corner, edges) = corners[i];
+        if mask & corner != 0 && mask & edges != edges {
+            return false;
+        }
+        i += 1;
+    }
+    true
+}
+
+// The 47 blob masks in ascending order. A blob atlas's `wall_N` sprite is the tile for `BLOB_MASKS[N]`.
+pub const BLOB_MASKS: [u8; BLOB_TILE_COUNT] = {
+    let mut masks = [0u8; BLOB_TILE_COUNT];
+    let mut count = 0;
+    let mut mask = 0usize;
+    while mask < 256 {
+        if is_reduced_blob_mask(mask as u8) {
+            masks[count] = mask as u8;
+            count += 1;
+        }
+        mask += 1;
+    }
+    assert!(count == BLOB_TILE_COUNT);
+    masks
+};
+
+// Inverse of `BLOB_MASKS`. Masks that `blob_mask` never produces map to tile 0.
+const BLOB_INDEX_BY_MASK: [u8; 256] = {
+    let mut indices = [0u8; 256];
+    let mut i = 0;
+    while i < BLOB_TILE_COUNT {
+        indices[BLOB_MASKS[i] as usize] = i as u8;
+        i += 1;
+    }
+    indices
+};
+
+// Whether a wall belongs to the maze's outer boundary or to a block inside it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WallKind {
+    // Connected to the edge of the map through other walls. Drawn with the double-line border art.
+    Border,
+    // A free-standing block that doesn't reach the map edge. Drawn with the single-line art.
+    Interior,
+}
+
+// Everything a renderer needs to pick the image for one wall cell.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct WallTile {
+    pub mask: u8,                     // Mask computed with the autotiler's scheme.
+    pub index: usize,                 // Tile image index for that mask.
+    pub kind: WallKind,               // Border or interior art.
+    pub door_side: Option<Direction>, // Side facing a ghost house door, for the notch pieces.
+}
+
+// Keeps the wall tile of every cell in sync with a level map.
+// Renderers look tiles up with `tile_at`; editors report edits through `update_cell`
+// so only the touched cell and its neighbors are recomputed.
+#[derive(Debug, Clone)]
+pub struct WallAutotiler {
+    scheme: TileScheme,
+    masks: Grid<Option<u8>>, // `Some(mask)` for wall cells, `None` for everything else.
+    door_sides: Grid<Option<Direction>>, // Direction of an adjacent door tile, if any.
+    border: Grid<bool>,      // True for wall cells connected to the map edge.
+}
+
+impl WallAutotiler {
+    // Computes 4-neighbor masks for the whole map.
+    pub fn new(map: &Grid<u8>) -> Self {
+        Self::with_scheme(map, TileScheme::default())
+    }
+
+    // Computes masks for the whole map using the given scheme.
+    pub fn with_scheme(map: &Grid<u8>, scheme: TileScheme) -> Self {
+        let mut autotiler = WallAutotiler {
+            scheme,
+            masks: Grid::new(map.width(), map.height(), None),
+            door_sides: Grid::new(map.width(), map.height(), None),
+            border: Grid::new(map.width(), map.height(), false),
+        };
+        autotiler.rebuild(map);
+        autotiler
+    }
+
+    pub fn scheme(&self) -> TileScheme {
+        self.scheme
+    }
+
+    // Recomputes every cell, e.g. after loading or clearing a level.
+    pub fn rebuild(&mut self, map: &Grid<u8>) {
+        if self.masks.width() != map.width() || self.masks.height() != map.height() {
+            self.masks = Grid::new(map.width(), map.height(), None);
+            self.door_sides = Grid::new(map.width(), map.height(), None);
+            self.border = Grid::new(map.width(), map.height(), false);
+        }
+        for (x, y, _) in map.iter() {
+            self.refresh(map, x, y);
+        }
+        self.classify(map);
+    }
+
+    // Recomputes the cell at (x, y) and its eight neighbors, whose masks and door notches
+    // depend on it. Border classification is connectivity-based, so it is redone for the
+    // whole map, but only when the edit added or removed a wall.
+    pub fn update_cell(&mut self, map: &Grid<u8>, x: usize, y: usize) {
+        if self.masks.width() != map.width() || self.masks.height() != map.height() {
+            self.rebuild(map);
+            return;
+        }
+        let was_wall = self.masks[(x, y)].is_some();
+        for dy in -1..=1 {
+            for dx in -1..=1 {
+                let (nx, ny) = (x as isize + dx, y as isize + dy);
+                if map.in_bounds(nx, ny) {
+                    self.refresh(map, nx as usize, ny as usize);
+                }
+            }
+        }
+        if was_wall != self.masks[(x, y)].is_some() {
+            self.classify(map);
+        }
+    }
+
+    // The wall mask at (x, y), or `None` if that cell isn't a wall or is out of bounds.
+    pub fn mask_at(&self, x: usize, y: usize) -> Option<u8> {
+        self.masks.get(x as isize, y as isize).copied().flatten()
+    }
+
+    // The tile to draw at (x, y), or `None` if that cell isn't a wall.
+    pub fn tile_at(&self, x: usize, y: usize) -> Option<WallTile> {
+        let mask = self.mask_at(x, y)?;
+        Some(WallTile {
+            mask,
+            index: self.scheme.tile_index(mask),
+            kind: if self.border[(x, y)] {
+                WallKind::Border
+            } else {
+                WallKind::Interior
+            },
+            door_side: self.door_sides[(x, y)],
+        })
+    }
+
+    // Recomputes a single cell.
+    fn refresh(&mut self, map: &Grid<u8>, x: usize, y: usize) {
+        let is_wall = map[(x, y)] == 1;
+        self.masks[(x, y)] = is_wall.then(|| self.scheme.mask(map, x, y));
+        self.door_sides[(x, y)] = if is_wall { door_side(map, x, y) } else { None };
+    }
+
+    // Flood-fills from every wall on the map edge to mark the walls connected to it as border.
+    fn classify(&mut self, map: &Grid<u8>) {
+        self.border = Grid::new(map.width(), map.height(), false);
+        let mut stack: Vec<(usize, usize)> = map
+            .iter()
+            .filter(|&(x, y, &tile)| {
+                tile == 1 && (x == 0 || y == 0 || x == map.width() - 1 || y == map.height() - 1)
+            })
+            .map(|(x, y, _)| (x, y))
+            .collect();
+        while let Some((x, y)) = stack.pop() {
+            if self.border[(x, y)] {
+                continue;
+            }
+            self.border[(x, y)] = true;
+            for (_, nx, ny, &tile) in map.neighbors(x as isize, y as isize) {
+                if tile == 1 && !self.border[(nx, ny)] {
+                    stack.push((nx, ny));
+                }
+            }
+        }
+    }
+}
+
+// Finds the side of a wall cell that touches a ghost house door (tile 4), if any.
+// Edge neighbors win over diagonals; a diagonal door counts as being on its horizontal
+// side, which is where the wall ends next to the door opening.
+fn door_side(map: &Grid<u8>, x: usize, y: usize) -> Option<Direction> {
+    let (x, y) = (x as isize, y as isize);
+    let is_door = |dx: isize, dy: isize| map.get(x + dx, y + dy) == Some(&4);
+    let edges = [
+        Direction::North,
+        Direction::South,
+        Direction::West,
+        Direction::East,
+    ];
+    if let Some(&side) = edges.iter().find(|side| {
+        let (dx, dy) = side.delta();
+        is_door(dx, dy)
+    }) {
+        return Some(side);
+    }
+    for (dx, dy) in [(-1, -1), (1, -1), (-1, 1), (1, 1)] {
+        if is_door(dx, dy) {
+            return Some(if dx < 0 {
+                Direction::West
+            } else {
+                Direction::East
+            });
+        }
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::load_level_from_string;
+    use std::collections::BTreeSet;
+
+    const LEVEL: &str = include_str!("../resources/levels/level1.txt");
+
+    // The eight neighbors of the center of a 3x3 map, with the bit each one sets.
+    const NEIGHBORS: [(u8, usize, usize); 8] = [
+        (WALL_NORTH, 1, 0),
+        (WALL_SOUTH, 1, 2),
+        (WALL_WEST, 0, 1),
+        (WALL_EAST, 2, 1),
+        (WALL_NORTH_WEST, 0, 0),
+        (WALL_NORTH_EAST, 2, 0),
+        (WALL_SOUTH_WEST, 0, 2),
+        (WALL_SOUTH_EAST, 2, 2),
+    ];
+
+    // A wall in the middle of a 3x3 map with a wall on every neighbor set in `neighbors`.
+    fn surrounded_by(neighbors: u8) -> Grid<u8> {
+        let mut map = Grid::new(3, 3, 0);
+        map.set(1, 1, 1);
+        for (bit, x, y) in NEIGHBORS {
+            if neighbors & bit != 0 {
+                map.set(x as isize, y as isize, 1);
+            }
+        }
+        map
+    }
+
+    #[test]
+    fn blob_masks_cover_every_neighborhood_with_47_tiles() {
+        let masks: BTreeSet<u8> = (0..=255)
+            .map(|neighbors| TileScheme::Blob47.mask(&surrounded_by(neighbors), 1, 1))
+            .collect();
+        assert_eq!(masks.len(), BLOB_TILE_COUNT);
+        assert_eq!(masks.into_iter().collect::<Vec<_>>(), BLOB_MASKS);
+
+        let indices: BTreeSet<usize> = BLOB_MASKS
+            .iter()
+            .map(|&mask| TileScheme::Blob47.tile_index(mask))
+            .collect();
+        assert_eq!(indices, (0..BLOB_TILE_COUNT).collect());
+    }
+
+    #[test]
+    fn blob_diagonals_need_both_adjacent_edges() {
+        let corners = [
+            (WALL_NORTH_WEST, WALL_NORTH | WALL_WEST),
+            (WALL_NORTH_EAST, WALL_NORTH | WALL_EAST),
+            (WALL_SOUTH_WEST, WALL_SOUTH | WALL_WEST),
+            (WALL_SOUTH_EAST, WALL_SOUTH | WALL_EAST),
+        ];
+        for neighbors in 0..=255u8 {
+            let mask = blob_mask(&surrounded_by(neighbors), 1, 1);
+            // Edge bits are always taken as they are.
+            assert_eq!(mask & 15, neighbors & 15, "neighbors {:08b}", neighbors);
+            for (corner, edges) in corners {
+                let expected = neighbors & corner != 0 && neighbors & edges == edges;
+                assert_eq!(mask & corner != 0, expected, "neighbors {:08b}", neighbors);
+            }
+        }
+    }
+
+    #[test]
+    fn cardinal_masks_ignore_diagonals() {
+        for neighbors in 0..=255u8 {
+            let map = surrounded_by(neighbors);
+            let mask = TileScheme::Cardinal16.mask(&map, 1, 1);
+            assert_eq!(mask, neighbors & 15);
+            assert_eq!(
+                TileScheme::Cardinal16.tile_index(mask),
+                (neighbors & 15) as usize
+            );
+        }
+    }
+
+    #[test]
+    fn update_cell_matches_a_full_rebuild() {
+        for scheme in [TileScheme::Cardinal16, TileScheme::Blob47] {
+            let mut map = load_level_from_string(LEVEL);
+            let mut autotiler = WallAutotiler::with_scheme(&map, scheme);
+            // Join an interior block to the border, cut a wall off the border, put a door
+            // between two walls, open up the map's corner and split the block off again.
+            for (x, y, tile) in [(1, 2, 1), (0, 13, 0), (3, 5, 4), (0, 0, 0), (1, 2, 2)] {
+                map.set(x as isize, y as isize, tile);
+                autotiler.update_cell(&map, x, y);
+                let rebuilt = WallAutotiler::with_scheme(&map, scheme);
+                for (cx, cy, _) in map.iter() {
+                    assert_eq!(
+                        autotiler.tile_at(cx, cy),
+                        rebuilt.tile_at(cx, cy),
+                        "{:?} at ({}, {}) after setting ({}, {})",
+                        scheme,
+                        cx,
+                        cy,
+                        x,
+                        y
+                    );
+                }
+            }
+        }
+    }
+
+    fn kind_at(autotiler: &WallAutotiler, x: usize, y: usize) -> WallKind {
+        autotiler.tile_at(x, y).unwrap().kind
+    }
+
+    #[test]
+    fn walls_reaching_the_edge_are_border() {
+        let map = load_level_from_string(
+            "\
+#####
+#   #
+# # #
+#   #
+##  #
+ #   ",
+        );
+        let autotiler = WallAutotiler::new(&map);
+        assert_eq!(kind_at(&autotiler, 0, 0), WallKind::Border);
+        // Only touches the edge through other walls.
+        assert_eq!(kind_at(&autotiler, 1, 4), WallKind::Border);
+        assert_eq!(kind_at(&autotiler, 2, 2), WallKind::Interior);
+        assert_eq!(autotiler.tile_at(1, 1), None);
+    }
+
+    #[test]
+    fn interior_blocks_become_border_when_joined_to_it() {
+        let mut map = load_level_from_string(
+            "\
+#####
+#   #
+# ###
+#   #
+#####",
+        );
+        let mut autotiler = WallAutotiler::new(&map);
+        assert_eq!(kind_at(&autotiler, 2, 2), WallKind::Border);
+
+        map.set(3, 2, 0);
+        autotiler.update_cell(&map, 3, 2);
+        assert_eq!(kind_at(&autotiler, 2, 2), WallKind::Interior);
+    }
+
+    #[test]
+    fn doors_are_found_on_every_side() {
+        let sides = [
+            ((2, 1), Some(Direction::North)),
+            ((2, 3), Some(Direction::South)),
+            ((1, 2), Some(Direction::West)),
+            ((3, 2), Some(Direction::East)),
+            ((1, 1), Some(Direction::West)),
+            ((3, 3), Some(Direction::East)),
+            ((0, 0), None),
+        ];
+        for ((door_x, door_y), expected) in sides {
+            let mut map = Grid::new(5, 5, 0);
+            map.set(2, 2, 1);
+            map.set(door_x, door_y, 4);
+            assert_eq!(
+                door_side(&map, 2, 2),
+                expected,
+                "door at ({}, {})",
+                door_x,
+                door_y
+            );
+            let autotiler = WallAutotiler::new(&map);
+            assert_eq!(autotiler.tile_at(2, 2).unwrap().door_side, expected);
+        }
+    }
+
+    #[test]
+    fn edge_doors_win_over_diagonal_ones() {
+        let mut map = Grid::new(3, 3, 0);
+        map.set(1, 1, 1);
+        map.set(0, 0, 4);
+        map.set(1, 2, 4);
+        assert_eq!(door_side(&map, 1, 1), Some(Direction::South));
+    }
+}
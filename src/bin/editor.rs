@@ -1,6 +1,6 @@
 use ggez::event::{self, EventHandler};
 use ggez::glam::Vec2;
-use ggez::graphics::{self, Color, DrawParam, Image, Rect, Text};
+use ggez::graphics::{self, Color, DrawParam, Rect};
 use ggez::input::keyboard::{KeyCode, KeyInput};
 use ggez::input::mouse::MouseButton;
 use ggez::{Context, ContextBuilder, GameResult};
@@ -16,45 +16,35 @@ enum EditorMode {
 
 // Editor state
 struct EditorState {
-    wall_images: Vec<Image>,
-    sprite_sheet: Image,
-    display_map: Vec<Vec<u8>>,
-    level_map: Vec<Vec<u8>>,
+    atlas: SpriteAtlas,
+    wall_tileset: WallTileset,
+    level_map: Grid<u8>,
+    walls: WallAutotiler,
+    maze_renderer: MazeRenderer, // Cached wall/door layer and dot instances.
+    highlight_mesh: graphics::Mesh, // Cursor outline, built once and moved with `dest`.
+    map_outline_mesh: graphics::Mesh,
+    spawn_previews: Vec<(Vec2, Animator)>, // Animated actors at their spawn points (tile units).
     small_dot_rect: Rect,
     big_dot_rect: Rect,
     current_tool: u8, // 0: Empty, 1: Wall, 2: Small Dot, 3: Big Dot, 4: Door
     filepath: String,
     mode: EditorMode,
-    font: String,
-    menu_options: Vec<(String, Rect)>,
+    font: BitmapFont, // Label font, drawn at integer scales on the 448x320 canvas.
+    menu_options: Vec<(String, Rect)>, // Labels and their click bounds, laid out once.
+    menu_mesh: graphics::Mesh, // Outlines of every menu option.
     symmetry_horizontal: bool,
     symmetry_vertical: bool,
+    viewport: Viewport, // Fits the 448x320 canvas into the window; F2 toggles integer/fit scaling.
+    bindings: Bindings, // Keys for each tool and command, overridable in the user's bindings file.
 }
 
 impl EditorState {
     fn new(ctx: &mut Context, filepath: &str) -> GameResult<EditorState> {
-        let mut wall_images = Vec::with_capacity(16);
-        for i in 0..16 {
-            let path = format!("/walls/wall_{}.png", i);
-            wall_images.push(Image::from_path(ctx, &path)?);
-        }
-
-        let sprite_sheet = Image::from_path(ctx, "/player.png")?;
-        let sprite_width_normalized = 1.0 / 6.0;
-        let sprite_height_normalized = 1.0;
+        let atlas = SpriteAtlas::load(ctx, "/atlas.json")?;
+        let wall_tileset = WallTileset::from_atlas(&atlas)?;
 
-        let small_dot_rect = Rect::new(
-            4.0 * sprite_width_normalized,
-            0.0,
-            sprite_width_normalized,
-            sprite_height_normalized,
-        );
-        let big_dot_rect = Rect::new(
-            5.0 * sprite_width_normalized,
-            0.0,
-            sprite_width_normalized,
-            sprite_height_normalized,
-        );
+        let small_dot_rect = atlas.sprite("dot_small")?;
+        let big_dot_rect = atlas.sprite("dot_big")?;
 
         let mut level_map = if let Ok(mut file) = ctx.fs.open(filepath) {
             let mut content = String::new();
@@ -72,21 +62,68 @@ impl EditorState {
 
         // Enforce ghost house initially
         Self::enforce_ghost_house(&mut level_map);
+        let walls = WallAutotiler::with_scheme(&level_map, wall_tileset.scheme());
 
-        let font = "LiberationMono-Regular".to_string();
+        // The maze layer follows the window's resolution, see `draw`.
+        let maze_renderer = MazeRenderer::new(ctx, &atlas, &level_map, 1.0);
+        let highlight_mesh = graphics::Mesh::new_rectangle(
+            ctx,
+            graphics::DrawMode::stroke(1.0),
+            Rect::new(0.0, 0.0, TILE_SIZE, TILE_SIZE),
+            Color::new(1.0, 0.0, 0.0, 0.8),
+        )?;
+        let map_outline_mesh = graphics::Mesh::new_rectangle(
+            ctx,
+            graphics::DrawMode::stroke(1.0),
+            Rect::new(
+                MAP_OFFSET_X - 1.0,
+                MAP_OFFSET_Y - 1.0,
+                28.0 * TILE_SIZE + 2.0,
+                31.0 * TILE_SIZE + 2.0,
+            ),
+            Color::WHITE,
+        )?;
 
-        let menu_options = vec![
-            ("New Level".to_string(), Rect::new(0.0, 0.0, 0.0, 0.0)),
-            ("Load Level".to_string(), Rect::new(0.0, 0.0, 0.0, 0.0)),
-            ("Save Level".to_string(), Rect::new(0.0, 0.0, 0.0, 0.0)),
-            ("Resume Editing".to_string(), Rect::new(0.0, 0.0, 0.0, 0.0)),
-        ];
+        // Show the player and ghosts where the game spawns them, using the game's animations.
+        let animations = AnimationLibrary::load(ctx, "/animations.json", &atlas)?;
+        let mut spawn_previews = vec![(
+            Vec2::new(PLAYER_START.0, PLAYER_START.1),
+            animations.animator("player", Direction::West.name())?,
+        )];
+        for &(name, (x, y), direction) in &GHOST_STARTS {
+            let state = GhostMode::Normal.animation_state(direction);
+            spawn_previews.push((Vec2::new(x, y), animations.animator(name, &state)?));
+        }
+
+        let font = BitmapFont::load(ctx, "/fonts/small.json")?;
+        let (bindings, bindings_error) = Bindings::load_user(ctx, EDITOR_BINDINGS);
+        if let Some(e) = bindings_error {
+            eprintln!("Ignoring invalid key bindings in {}", e);
+        }
+
+        // The menu never changes, so its options are centered and outlined once.
+        let mut menu_options = Vec::new();
+        let mut menu_outlines = graphics::MeshBuilder::new();
+        let labels = ["New Level", "Load Level", "Save Level", "Resume Editing"];
+        for (i, label) in labels.into_iter().enumerate() {
+            let dims = font.measure(label, 2);
+            let center_x = ((448.0 - dims.x) / 2.0).floor();
+            let y = 100.0 + 30.0 * i as f32;
+            let bounds = Rect::new(center_x - 10.0, y, dims.x + 20.0, dims.y);
+            menu_outlines.rectangle(graphics::DrawMode::stroke(1.0), bounds, Color::WHITE)?;
+            menu_options.push((label.to_string(), bounds));
+        }
+        let menu_mesh = graphics::Mesh::from_data(ctx, menu_outlines.build());
 
-        let mut state = EditorState {
-            wall_images,
-            sprite_sheet,
-            display_map: vec![],
+        let state = EditorState {
+            atlas,
+            wall_tileset,
             level_map,
+            walls,
+            maze_renderer,
+            highlight_mesh,
+            map_outline_mesh,
+            spawn_previews,
             small_dot_rect,
             big_dot_rect,
             current_tool: 1,
@@ -94,70 +131,68 @@ impl EditorState {
             mode: EditorMode::Menu,
             font,
             menu_options,
+            menu_mesh,
             symmetry_horizontal: false,
             symmetry_vertical: false,
+            viewport: Viewport::new(448.0, 320.0, ScaleMode::Integer),
+            bindings,
         };
-        state.update_display_map();
         Ok(state)
     }
 
-    fn enforce_ghost_house(map: &mut Vec<Vec<u8>>) {
-        // Enforce the ghost house structure based on level1.txt coordinates
-        
+    // Returns the cells that had to be changed so their wall masks can be refreshed.
+    fn enforce_ghost_house(map: &mut Grid<u8>) -> Vec<(usize, usize)> {
+        // Enforce the ghost house structure based on level1.txt coordinates.
+        // Cells outside smaller maps are skipped.
+        let mut changed = Vec::new();
+        let mut put = |x: isize, y: isize, tile: u8| {
+            if map.get(x, y).is_some_and(|&current| current != tile) {
+                map.set(x, y, tile);
+                changed.push((x as usize, y as usize));
+            }
+        };
+
         // Door (Row 11)
-        if map.len() > 11 {
-            map[11][13] = 4; // Door
-            map[11][14] = 4; // Door
-        }
+        put(13, 11, 4); // Door
+        put(14, 11, 4); // Door
 
         // Top Walls (Row 12)
-        if map.len() > 12 {
-            for x in 10..=12 { map[12][x] = 1; }
-            map[12][13] = 0; // Gap under door
-            map[12][14] = 0; // Gap under door
-            for x in 15..=17 { map[12][x] = 1; }
+        for x in 10..=12 {
+            put(x, 12, 1);
+        }
+        put(13, 12, 0); // Gap under door
+        put(14, 12, 0); // Gap under door
+        for x in 15..=17 {
+            put(x, 12, 1);
         }
 
         // Side Walls and Interior (Rows 13-15)
         for y in 13..=15 {
-            if map.len() > y {
-                map[y][10] = 1; // Left Wall
-                map[y][17] = 1; // Right Wall
-                for x in 11..=16 { map[y][x] = 0; } // Interior Empty
-            }
+            put(10, y, 1); // Left Wall
+            put(17, y, 1); // Right Wall
+            for x in 11..=16 {
+                put(x, y, 0);
+            } // Interior Empty
         }
 
         // Bottom Wall (Row 16)
-        if map.len() > 16 {
-            for x in 10..=17 { map[16][x] = 1; }
+        for x in 10..=17 {
+            put(x, 16, 1);
         }
+
+        changed
     }
 
-    fn update_display_map(&mut self) {
-        self.display_map = self.level_map.clone();
-        for (y, row) in self.level_map.iter().enumerate() {
-            for (x, &tile_value) in row.iter().enumerate() {
-                if tile_value == 1 {
-                    let mut wall_mask: u8 = 0;
-                    if is_wall_at(x as isize, y as isize - 1, &self.level_map) {
-                        wall_mask += 1;
-                    }
-                    if is_wall_at(x as isize, y as isize + 1, &self.level_map) {
-                        wall_mask += 2;
-                    }
-                    if is_wall_at(x as isize - 1, y as isize, &self.level_map) {
-                        wall_mask += 4;
-                    }
-                    if is_wall_at(x as isize + 1, y as isize, &self.level_map) {
-                        wall_mask += 8;
-                    }
-                    self.display_map[y][x] = wall_mask + WALL_CODE_OFFSET;
-                }
-            }
-        }
+    // Enforces the ghost house and recomputes every wall mask, for freshly loaded or cleared maps.
+    fn reset_level(&mut self) {
+        Self::enforce_ghost_house(&mut self.level_map);
+        self.walls.rebuild(&self.level_map);
+        self.maze_renderer.invalidate_walls();
+        self.maze_renderer.invalidate_dots();
     }
 
-    fn save_level(&self, _ctx: &mut Context) -> GameResult {
+    // On-disk path of the level file inside the resources directory.
+    fn level_file_path(&self) -> path::PathBuf {
         let resource_dir = if let Ok(manifest_dir) = env::var("CARGO_MANIFEST_DIR") {
             let mut path = path::PathBuf::from(manifest_dir);
             path.push("resources");
@@ -165,37 +200,60 @@ impl EditorState {
         } else {
             path::PathBuf::from("./resources")
         };
-        
+
         let relative_path = if self.filepath.starts_with("/") {
-             &self.filepath[1..]
+            &self.filepath[1..]
         } else {
-             &self.filepath
+            &self.filepath
         };
 
-        let full_path = resource_dir.join(relative_path);
-        
+        resource_dir.join(relative_path)
+    }
+
+    fn save_level(&self, _ctx: &mut Context) -> GameResult {
+        let full_path = self.level_file_path();
+
         let content = save_level_to_string(&self.level_map);
-        
+
         if let Ok(mut file) = std::fs::File::create(&full_path) {
-             let _ = file.write_all(content.as_bytes());
-             println!("Saved level to {:?}", full_path);
+            let _ = file.write_all(content.as_bytes());
+            println!("Saved level to {:?}", full_path);
         } else {
-             eprintln!("Failed to save level to {:?}", full_path);
+            eprintln!("Failed to save level to {:?}", full_path);
         }
-        
+
         Ok(())
     }
 
+    // Writes the vector wall outlines next to the level file, e.g. `level1.svg`.
+    fn export_svg(&self) {
+        let full_path = self.level_file_path().with_extension("svg");
+
+        let outlines = outline::maze_outlines(
+            &self.level_map,
+            outline::WALL_INSET,
+            outline::WALL_CORNER_RADIUS,
+            outline::WALL_CORNER_SEGMENTS,
+        );
+        let content = outline::outlines_to_svg(
+            &outlines,
+            self.level_map.width(),
+            self.level_map.height(),
+            TILE_SIZE,
+            "#fc4848",
+        );
+
+        if let Ok(mut file) = std::fs::File::create(&full_path) {
+            let _ = file.write_all(content.as_bytes());
+            println!("Exported walls to {:?}", full_path);
+        } else {
+            eprintln!("Failed to export walls to {:?}", full_path);
+        }
+    }
+
     fn update_menu(&mut self, ctx: &mut Context) -> GameResult {
         if ctx.mouse.button_pressed(MouseButton::Left) {
-            let mouse_pos = ctx.mouse.position();
-            
-            let window = ctx.gfx.window();
-            let size = window.inner_size();
-            let scale_x = size.width as f32 / 448.0;
-            let scale_y = size.height as f32 / 320.0;
-            
-            let scaled_pos = Vec2::new(mouse_pos.x / scale_x, mouse_pos.y / scale_y);
+            let scaled_pos = self.viewport.mouse_position(ctx);
 
             let mut clicked_index = None;
             for (i, (_, rect)) in self.menu_options.iter().enumerate() {
@@ -209,8 +267,7 @@ impl EditorState {
                 match i {
                     0 => {
                         self.level_map = create_default_map();
-                        Self::enforce_ghost_house(&mut self.level_map);
-                        self.update_display_map();
+                        self.reset_level();
                         self.mode = EditorMode::Editing;
                     }
                     1 => {
@@ -218,8 +275,7 @@ impl EditorState {
                             let mut content = String::new();
                             if file.read_to_string(&mut content).is_ok() {
                                 self.level_map = load_level_from_string(&content);
-                                Self::enforce_ghost_house(&mut self.level_map);
-                                self.update_display_map();
+                                self.reset_level();
                             }
                         }
                         self.mode = EditorMode::Editing;
@@ -237,7 +293,7 @@ impl EditorState {
         Ok(())
     }
 
-    fn draw_menu(&mut self, canvas: &mut graphics::Canvas, ctx: &mut Context) -> GameResult {
+    fn draw_menu(&self, canvas: &mut graphics::Canvas) {
         canvas.draw(
             &graphics::Quad,
             graphics::DrawParam::new()
@@ -245,89 +301,66 @@ impl EditorState {
                 .scale(Vec2::new(448.0, 320.0))
                 .color(Color::new(0.1, 0.1, 0.1, 1.0)),
         );
-        let mut text = Text::new("RUST-PAC EDITOR");
-        text.set_font(self.font.clone()).set_scale(32.0);
-        let text_dims = text.measure(ctx)?;
-        let center_x = (448.0 - text_dims.x) / 2.0;
-        canvas.draw(&text, Vec2::new(center_x, 40.0));
-
-        let mut y = 100.0;
-        for (i, (label, _)) in self.menu_options.clone().iter().enumerate() {
-            let mut menu_item = Text::new(label.as_str());
-            menu_item.set_font(self.font.clone()).set_scale(20.0);
-            let dims = menu_item.measure(ctx)?;
-            let center_x = (448.0 - dims.x) / 2.0;
-            let dest = Vec2::new(center_x, y);
-
-            let bounds = Rect::new(center_x - 10.0, y, dims.x + 20.0, dims.y);
-            self.menu_options[i].1 = bounds;
-            
-            let mesh = graphics::Mesh::new_rectangle(
-                ctx, 
-                graphics::DrawMode::stroke(1.0), 
-                bounds, 
-                Color::WHITE
-            )?;
-            canvas.draw(&mesh, Vec2::new(0.0, 0.0));
-
-            canvas.draw(&menu_item, dest);
-            y += 30.0;
+        let title = "RUST-PAC EDITOR";
+        let title_dims = self.font.measure(title, 4);
+        let center_x = ((448.0 - title_dims.x) / 2.0).floor();
+        self.font
+            .draw(canvas, title, Vec2::new(center_x, 40.0), 4, Color::WHITE);
+
+        canvas.draw(&self.menu_mesh, Vec2::new(0.0, 0.0));
+        for (label, bounds) in &self.menu_options {
+            let dest = Vec2::new(bounds.x + 10.0, bounds.y);
+            self.font.draw(canvas, label, dest, 2, Color::WHITE);
         }
-
-        Ok(())
     }
 
     fn update_editor(&mut self, ctx: &mut Context) -> GameResult {
-        let mouse = ctx.mouse.position();
-        
-        let window = ctx.gfx.window();
-        let size = window.inner_size();
-        let scale_x = size.width as f32 / 448.0;
-        let scale_y = size.height as f32 / 320.0;
-
-        let scaled_mouse_x = mouse.x / scale_x;
-        let scaled_mouse_y = mouse.y / scale_y;
+        let mouse = self.viewport.mouse_position(ctx);
+        let scaled_mouse_x = mouse.x;
+        let scaled_mouse_y = mouse.y;
 
         if ctx.mouse.button_pressed(MouseButton::Left) {
-            let map_offset_x = 112.0;
-            let map_x = ((scaled_mouse_x - map_offset_x) / TILE_SIZE).floor() as isize;
-            let map_y = ((scaled_mouse_y - MAZE_OFFSET_Y) / TILE_SIZE).floor() as isize;
-            
-            if map_x >= 0 && map_y >= 0 {
+            let map_x = ((scaled_mouse_x - MAP_OFFSET_X) / TILE_SIZE).floor() as isize;
+            let map_y = ((scaled_mouse_y - MAP_OFFSET_Y) / TILE_SIZE).floor() as isize;
+
+            if self.level_map.in_bounds(map_x, map_y) {
                 let x = map_x as usize;
                 let y = map_y as usize;
-                if y < self.level_map.len() && x < self.level_map[0].len() {
-                    let mut points = vec![(x, y)];
-                    
-                    if self.symmetry_horizontal {
-                        let sym_x = self.level_map[0].len().saturating_sub(1).saturating_sub(x);
-                        points.push((sym_x, y));
-                    }
-                    
-                    if self.symmetry_vertical {
-                        let sym_y = self.level_map.len().saturating_sub(1).saturating_sub(y);
-                        points.push((x, sym_y));
-                    }
-                    
-                    if self.symmetry_horizontal && self.symmetry_vertical {
-                        let sym_x = self.level_map[0].len().saturating_sub(1).saturating_sub(x);
-                        let sym_y = self.level_map.len().saturating_sub(1).saturating_sub(y);
-                        points.push((sym_x, sym_y));
-                    }
+                let mut points = vec![(x, y)];
 
-                    let mut changed = false;
-                    for (px, py) in points {
-                        if self.level_map[py][px] != self.current_tool {
-                            self.level_map[py][px] = self.current_tool;
-                            changed = true;
-                        }
+                if self.symmetry_horizontal {
+                    let sym_x = self.level_map.width() - 1 - x;
+                    points.push((sym_x, y));
+                }
+
+                if self.symmetry_vertical {
+                    let sym_y = self.level_map.height() - 1 - y;
+                    points.push((x, sym_y));
+                }
+
+                if self.symmetry_horizontal && self.symmetry_vertical {
+                    let sym_x = self.level_map.width() - 1 - x;
+                    let sym_y = self.level_map.height() - 1 - y;
+                    points.push((sym_x, sym_y));
+                }
+
+                let mut changed = Vec::new();
+                for (px, py) in points {
+                    if self.level_map[(px, py)] != self.current_tool {
+                        self.level_map[(px, py)] = self.current_tool;
+                        changed.push((px, py));
                     }
-                    
-                    if changed {
-                        // Re-enforce ghost house protection
-                        Self::enforce_ghost_house(&mut self.level_map);
-                        self.update_display_map();
+                }
+
+                if !changed.is_empty() {
+                    // Re-enforce ghost house protection
+                    changed.extend(Self::enforce_ghost_house(&mut self.level_map));
+                    // Only the edited cells and their neighbors need new wall masks.
+                    for (cx, cy) in changed {
+                        self.walls.update_cell(&self.level_map, cx, cy);
                     }
+                    self.maze_renderer.invalidate_walls();
+                    self.maze_renderer.invalidate_dots();
                 }
             }
         }
@@ -337,30 +370,32 @@ impl EditorState {
     fn draw_editor(&mut self, canvas: &mut graphics::Canvas, ctx: &mut Context) -> GameResult {
         let legend_text = [
             "Controls: [1] Wall | [2] Dot | [3] Big Dot | [4] Fence | [0] Erase",
-            "[S] Save | [Esc] Menu | [C] Clear | [H/V] Symmetry",
+            "[S] Save | [E] Export SVG | [Esc] Menu | [C] Clear | [H/V] Symmetry",
         ];
         let mut y = 5.0;
         for line in legend_text.iter() {
-            let mut text = Text::new(*line);
-            text.set_font(self.font.clone()).set_scale(10.0);
-            canvas.draw(&text, Vec2::new(5.0, y));
+            self.font
+                .draw(canvas, line, Vec2::new(5.0, y), 1, Color::WHITE);
             y += 12.0;
         }
 
-        let sym_text_str = format!("Symmetry: H:[{}] V:[{}]", 
-            if self.symmetry_horizontal { "ON" } else { "OFF" },
+        let sym_text_str = format!(
+            "Symmetry: H:[{}] V:[{}]",
+            if self.symmetry_horizontal {
+                "ON"
+            } else {
+                "OFF"
+            },
             if self.symmetry_vertical { "ON" } else { "OFF" }
         );
-        let mut sym_text = Text::new(sym_text_str);
-        sym_text.set_font(self.font.clone()).set_scale(10.0);
-        canvas.draw(&sym_text, Vec2::new(5.0, y));
+        self.font
+            .draw(canvas, &sym_text_str, Vec2::new(5.0, y), 1, Color::WHITE);
         y += 12.0;
-        
-        let mut tool_text = Text::new("Tool:");
-        tool_text.set_font(self.font.clone()).set_scale(10.0);
-        let tool_text_width = tool_text.measure(ctx)?.x;
-        canvas.draw(&tool_text, Vec2::new(5.0, y));
-        
+
+        let tool_text_width = self.font.measure("Tool:", 1).x;
+        self.font
+            .draw(canvas, "Tool:", Vec2::new(5.0, y), 1, Color::WHITE);
+
         let color = match self.current_tool {
             1 => Color::BLUE,
             2 => Color::YELLOW,
@@ -368,124 +403,134 @@ impl EditorState {
             4 => Color::MAGENTA,
             _ => Color::WHITE,
         };
-        let rect = graphics::Mesh::new_rectangle(
-            ctx, 
-            graphics::DrawMode::fill(), 
-            Rect::new(0.0, 0.0, 10.0, 10.0), 
-            color
-        )?;
-        canvas.draw(&rect, Vec2::new(5.0 + tool_text_width + 5.0, y));
+        canvas.draw(
+            &graphics::Quad,
+            DrawParam::new()
+                .dest(Vec2::new(5.0 + tool_text_width + 5.0, y))
+                .scale(Vec2::new(10.0, 10.0))
+                .color(color),
+        );
 
-        let map_offset_x = 112.0; // (448.0 - (28 * 8)) / 2
-        let map_width = 28.0 * TILE_SIZE;
-        let map_height = 31.0 * TILE_SIZE;
+        canvas.draw(&self.map_outline_mesh, Vec2::new(0.0, 0.0));
 
-        let map_outline = graphics::Mesh::new_rectangle(
-            ctx,
-            graphics::DrawMode::stroke(1.0),
-            Rect::new(map_offset_x - 1.0, MAZE_OFFSET_Y - 1.0, map_width + 2.0, map_height + 2.0),
-            Color::WHITE
-        )?;
-        canvas.draw(&map_outline, Vec2::new(0.0, 0.0));
-
-        for (y, row) in self.display_map.iter().enumerate() {
-            for (x, &tile_type) in row.iter().enumerate() {
-                let dest_x = (x as f32 * TILE_SIZE) + map_offset_x;
-                let dest_y = (y as f32 * TILE_SIZE) + MAZE_OFFSET_Y;
-                let dest_point = Vec2::new(dest_x, dest_y);
-
-                match tile_type {
-                    code @ WALL_CODE_OFFSET..=255 => {
-                        let wall_index = (code - WALL_CODE_OFFSET) as usize;
-                        if wall_index < self.wall_images.len() {
-                            canvas.draw(&self.wall_images[wall_index], dest_point);
-                        }
-                    }
-                    2 => {
-                        let params = DrawParam::new().dest(dest_point).src(self.small_dot_rect);
-                        canvas.draw(&self.sprite_sheet, params);
-                    }
-                    3 => {
-                        let params = DrawParam::new().dest(dest_point).src(self.big_dot_rect);
-                        canvas.draw(&self.sprite_sheet, params);
-                    }
-                    4 => {
-                        let rect = graphics::Mesh::new_rectangle(
-                            ctx,
-                            graphics::DrawMode::fill(),
-                            Rect::new(dest_x, dest_y, TILE_SIZE, TILE_SIZE),
-                            Color::MAGENTA,
-                        )?;
-                        canvas.draw(&rect, Vec2::new(0.0, 0.0));
-                    }
-                    _ => {}
-                }
-            }
+        // Walls, doors and dots come from the cached layer refreshed in `prepare_maze`.
+        self.maze_renderer
+            .draw(canvas, Vec2::new(MAP_OFFSET_X, MAP_OFFSET_Y));
+
+        for (tile_pos, animation) in &self.spawn_previews {
+            let dest = Vec2::new(
+                MAP_OFFSET_X + (tile_pos.x - 0.5) * TILE_SIZE,
+                MAP_OFFSET_Y + (tile_pos.y - 0.5) * TILE_SIZE,
+            );
+            animation.draw(canvas, self.atlas.image(), dest);
         }
-        
+
         // Draw cursor highlight
-        let mouse = ctx.mouse.position();
-        let window = ctx.gfx.window();
-        let size = window.inner_size();
-        let scale_x = size.width as f32 / 448.0;
-        let scale_y = size.height as f32 / 320.0;
-        let scaled_mouse_x = mouse.x / scale_x;
-        let scaled_mouse_y = mouse.y / scale_y;
-        
-        let map_x = ((scaled_mouse_x - map_offset_x) / TILE_SIZE).floor() as isize;
-        let map_y = ((scaled_mouse_y - MAZE_OFFSET_Y) / TILE_SIZE).floor() as isize;
-
-        if map_x >= 0 && map_y >= 0 {
-             let x = map_x as usize;
-             let y = map_y as usize;
-             if y < self.display_map.len() && x < self.display_map[0].len() {
-                 let dest_x = (x as f32 * TILE_SIZE) + map_offset_x;
-                 let dest_y = (y as f32 * TILE_SIZE) + MAZE_OFFSET_Y;
-                 let highlight = graphics::Mesh::new_rectangle(
-                     ctx,
-                     graphics::DrawMode::stroke(1.0),
-                     Rect::new(dest_x, dest_y, TILE_SIZE, TILE_SIZE),
-                     Color::new(1.0, 0.0, 0.0, 0.8),
-                 )?;
-                 canvas.draw(&highlight, Vec2::new(0.0, 0.0));
-             }
+        let mouse = self.viewport.mouse_position(ctx);
+        let scaled_mouse_x = mouse.x;
+        let scaled_mouse_y = mouse.y;
+
+        let map_x = ((scaled_mouse_x - MAP_OFFSET_X) / TILE_SIZE).floor() as isize;
+        let map_y = ((scaled_mouse_y - MAP_OFFSET_Y) / TILE_SIZE).floor() as isize;
+
+        if self.level_map.in_bounds(map_x, map_y) {
+            let dest_x = (map_x as f32 * TILE_SIZE) + MAP_OFFSET_X;
+            let dest_y = (map_y as f32 * TILE_SIZE) + MAP_OFFSET_Y;
+            canvas.draw(&self.highlight_mesh, Vec2::new(dest_x, dest_y));
         }
-        
+
         Ok(())
     }
 
-    fn key_down_editor(&mut self, ctx: &mut Context, input: KeyInput) -> GameResult {
-        if let Some(keycode) = input.keycode {
-            match keycode {
-                KeyCode::Key1 => self.current_tool = 1,
-                KeyCode::Key2 => self.current_tool = 2,
-                KeyCode::Key3 => self.current_tool = 3,
-                KeyCode::Key4 => self.current_tool = 4,
-                KeyCode::Key0 | KeyCode::Grave => self.current_tool = 0,
-                KeyCode::H => self.symmetry_horizontal = !self.symmetry_horizontal,
-                KeyCode::V => self.symmetry_vertical = !self.symmetry_vertical,
-                KeyCode::S => {
-                    self.save_level(ctx)?;
-                }
-                KeyCode::C => {
-                    self.level_map = create_default_map();
-                    Self::enforce_ghost_house(&mut self.level_map);
-                    self.update_display_map();
+    // Re-renders the cached maze layer and dot instances if an edit invalidated them.
+    // Runs before the frame canvas is created so the layer is finished first.
+    fn prepare_maze(&mut self, ctx: &mut Context) -> GameResult {
+        self.maze_renderer
+            .update_walls(ctx, &self.level_map, |canvas| {
+                render::draw_wall_tiles(
+                    canvas,
+                    &self.atlas,
+                    &self.wall_tileset,
+                    &self.walls,
+                    &self.level_map,
+                );
+                // Doors are static too, so they live in the layer as plain quads.
+                for (x, y, &tile) in self.level_map.iter() {
+                    if tile == 4 {
+                        canvas.draw(
+                            &graphics::Quad,
+                            DrawParam::new()
+                                .dest(Vec2::new(x as f32 * TILE_SIZE, y as f32 * TILE_SIZE))
+                                .scale(Vec2::new(TILE_SIZE, TILE_SIZE))
+                                .color(Color::MAGENTA),
+                        );
+                    }
                 }
-                KeyCode::Escape => self.mode = EditorMode::Menu,
-                _ => {}
+                Ok(())
+            })?;
+        self.maze_renderer
+            .update_dots(&self.level_map, self.small_dot_rect, self.big_dot_rect);
+        Ok(())
+    }
+
+    fn action_editor(&mut self, ctx: &mut Context, action: Action) -> GameResult {
+        match action {
+            Action::ToolWall => self.current_tool = 1,
+            Action::ToolDot => self.current_tool = 2,
+            Action::ToolBigDot => self.current_tool = 3,
+            Action::ToolDoor => self.current_tool = 4,
+            Action::ToolEmpty => self.current_tool = 0,
+            Action::SymmetryHorizontal => self.symmetry_horizontal = !self.symmetry_horizontal,
+            Action::SymmetryVertical => self.symmetry_vertical = !self.symmetry_vertical,
+            Action::Save => {
+                self.save_level(ctx)?;
+            }
+            Action::ExportSvg => self.export_svg(),
+            Action::Clear => {
+                self.level_map = create_default_map();
+                self.reset_level();
             }
+            Action::Back => self.mode = EditorMode::Menu,
+            _ => {}
         }
         Ok(())
     }
 }
 
-fn create_default_map() -> Vec<Vec<u8>> {
-    vec![vec![0; 28]; 31]
+// Default keys of the editor's tools and commands; `bindings.json` in the editor's user
+// config directory can rebind them.
+const EDITOR_BINDINGS: &[(Action, &[KeyCode])] = &[
+    (Action::ToolWall, &[KeyCode::Key1]),
+    (Action::ToolDot, &[KeyCode::Key2]),
+    (Action::ToolBigDot, &[KeyCode::Key3]),
+    (Action::ToolDoor, &[KeyCode::Key4]),
+    (Action::ToolEmpty, &[KeyCode::Key0, KeyCode::Grave]),
+    (Action::SymmetryHorizontal, &[KeyCode::H]),
+    (Action::SymmetryVertical, &[KeyCode::V]),
+    (Action::Save, &[KeyCode::S]),
+    (Action::ExportSvg, &[KeyCode::E]),
+    (Action::Clear, &[KeyCode::C]),
+    (Action::Back, &[KeyCode::Escape]),
+    (Action::ToggleScaling, &[KeyCode::F2]),
+];
+
+// Horizontal position of the map on the editor canvas: (448.0 - (28 * 8)) / 2
+const MAP_OFFSET_X: f32 = 112.0;
+// Vertical position of the map, below the legend. The game's `MAZE_OFFSET_Y` is smaller.
+const MAP_OFFSET_Y: f32 = TILE_SIZE * 5.0;
+
+fn create_default_map() -> Grid<u8> {
+    Grid::new(28, 31, 0)
 }
 
 impl EventHandler for EditorState {
     fn update(&mut self, ctx: &mut Context) -> GameResult {
+        let dt = ctx.time.delta().as_secs_f32();
+        // The previews are silent, so their frame events go unused.
+        for (_, animation) in &mut self.spawn_previews {
+            animation.update(dt);
+        }
+
         match self.mode {
             EditorMode::Menu => self.update_menu(ctx),
             EditorMode::Editing => self.update_editor(ctx),
@@ -493,12 +538,18 @@ impl EventHandler for EditorState {
     }
 
     fn draw(&mut self, ctx: &mut Context) -> GameResult {
+        if let EditorMode::Editing = self.mode {
+            let layer_scale = self.viewport.window_scale(ctx).ceil();
+            self.maze_renderer.set_layer_scale(layer_scale);
+            self.prepare_maze(ctx)?;
+        }
+
         let mut canvas = graphics::Canvas::from_frame(ctx, Color::BLACK);
-        canvas.set_screen_coordinates(Rect::new(0.0, 0.0, 448.0, 320.0));
+        self.viewport.apply(ctx, &mut canvas)?;
         canvas.set_sampler(graphics::Sampler::nearest_clamp());
 
         match self.mode {
-            EditorMode::Menu => self.draw_menu(&mut canvas, ctx)?,
+            EditorMode::Menu => self.draw_menu(&mut canvas),
             EditorMode::Editing => self.draw_editor(&mut canvas, ctx)?,
         }
 
@@ -506,14 +557,26 @@ impl EventHandler for EditorState {
         Ok(())
     }
 
-    fn key_down_event(&mut self, ctx: &mut Context, input: KeyInput, _repeated: bool) -> GameResult {
+    fn key_down_event(
+        &mut self,
+        ctx: &mut Context,
+        input: KeyInput,
+        _repeated: bool,
+    ) -> GameResult {
+        let Some(action) = input.keycode.and_then(|key| self.bindings.action(key)) else {
+            return Ok(());
+        };
+        if action == Action::ToggleScaling {
+            self.viewport.mode = self.viewport.mode.toggled();
+            return Ok(());
+        }
         match self.mode {
             EditorMode::Menu => {
-                if let Some(KeyCode::Escape) = input.keycode {
+                if action == Action::Back {
                     self.mode = EditorMode::Editing;
                 }
             }
-            EditorMode::Editing => self.key_down_editor(ctx, input)?,
+            EditorMode::Editing => self.action_editor(ctx, action)?,
         }
         Ok(())
     }
@@ -530,11 +593,16 @@ fn main() -> GameResult {
 
     let (mut ctx, event_loop) = ContextBuilder::new("rust_pac_editor", "Sir Marshall")
         .window_setup(ggez::conf::WindowSetup::default().title("RUST PAC EDITOR"))
-        .window_mode(ggez::conf::WindowMode::default().dimensions(1344.0, 960.0))
+        .window_mode(
+            ggez::conf::WindowMode::default()
+                .dimensions(1344.0, 960.0)
+                .resizable(true)
+                .min_dimensions(448.0, 320.0),
+        )
         .add_resource_path(resource_dir)
         .build()?;
 
     let state = EditorState::new(&mut ctx, "/levels/level1.txt")?;
 
     event::run(ctx, event_loop, state)
-}
\ No newline at end of file
+}
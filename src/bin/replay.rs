@@ -0,0 +1,134 @@
+// Plays a replay file back without a window and checks it still reproduces:
+//
+//     cargo run --bin replay -- resources/levels/level1.txt last_replay.json [--quiet]
+//
+// Prints the state hash of every tick (unless --quiet), then the final score, tick count,
+// deaths and state hash. Exits with an error as soon as a state hash recorded in the
+// replay doesn't match the playback, so gameplay changes that break old replays show up
+// as failures.
+//
+//     cargo run --bin replay -- resources/levels/level1.txt a.json --compare b.json
+//
+// Plays two replays of the same level side by side instead and reports the first tick and
+// state field where they differ, e.g. to track down where two peers desynced.
+use rust_pack::replay::play;
+use rust_pack::state_hash::RunTrace;
+use rust_pack::*;
+use std::env;
+use std::fs;
+use std::path::Path;
+use std::process::ExitCode;
+
+const USAGE: &str =
+    "usage: replay <level.txt> <replay.json> [--quiet] [--compare <other_replay.json>]";
+
+fn main() -> ExitCode {
+    let mut args = env::args().skip(1);
+    let mut quiet = false;
+    let mut compare = None;
+    let mut paths = Vec::new();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--quiet" => quiet = true,
+            "--compare" => compare = args.next(),
+            _ => paths.push(arg),
+        }
+    }
+    let [level_path, replay_path] = &paths[..] else {
+        eprintln!("{}", USAGE);
+        return ExitCode::FAILURE;
+    };
+    let result = match compare {
+        Some(other_path) => compare_runs(
+            Path::new(level_path),
+            Path::new(replay_path),
+            Path::new(&other_path),
+        ),
+        None => run(Path::new(level_path), Path::new(replay_path), quiet),
+    };
+    match result {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            eprintln!("{}", e);
+            ExitCode::FAILURE
+        }
+    }
+}
+
+fn load_level(path: &Path) -> Result<Grid<u8>, String> {
+    let content =
+        fs::read_to_string(path).map_err(|e| format!("Failed to read level {:?}: {}", path, e))?;
+    Ok(load_level_from_string(&content))
+}
+
+// Loads a replay and the simulation to play it back on.
+fn load_replay(level_map: Grid<u8>, path: &Path) -> Result<(Replay, Simulation), String> {
+    let replay = Replay::read(path).map_err(|e| e.to_string())?;
+    let sim = replay
+        .start(level_map)
+        .map_err(|e| format!("Replay {:?} doesn't fit the level: {}", path, e))?;
+    Ok((replay, sim))
+}
+
+fn run(level_path: &Path, replay_path: &Path, quiet: bool) -> Result<(), String> {
+    let (mut replay, mut sim) = load_replay(load_level(level_path)?, replay_path)?;
+
+    // The controller borrows the replay while it plays, so check against a copy.
+    let expected = replay.clone();
+    let mut divergence = None;
+    let length = replay.length;
+    let deaths = play(&mut sim, &mut replay, length, |sim| {
+        if divergence.is_some() {
+            return;
+        }
+        let tick = sim.tick_count();
+        let hash = sim.state_hash();
+        if !quiet {
+            println!("{} {:016x}", tick, hash);
+        }
+        if let Some(expected) = expected.expected_hash(tick)
+            && expected != hash
+        {
+            divergence = Some(format!(
+                "State hash diverges at tick {}: recorded {:016x}, played back {:016x}",
+                tick, expected, hash
+            ));
+        }
+    });
+
+    println!("score {}", sim.scoreboard.score());
+    println!("ticks {}", sim.tick_count());
+    println!("deaths {}", deaths);
+    println!("hash {:016x}", sim.state_hash());
+    match divergence {
+        Some(e) => Err(e),
+        None => Ok(()),
+    }
+}
+
+fn compare_runs(level_path: &Path, path: &Path, other_path: &Path) -> Result<(), String> {
+    let level_map = load_level(level_path)?;
+    let trace = trace_run(level_map.clone(), path)?;
+    let other_trace = trace_run(level_map, other_path)?;
+    match trace.first_divergence(&other_trace) {
+        Some(divergence) => Err(format!(
+            "Runs diverge at tick {} in {}",
+            divergence.tick, divergence.field
+        )),
+        None => {
+            println!("Runs match for all {} ticks", trace.len());
+            Ok(())
+        }
+    }
+}
+
+// Plays a replay back, keeping the state of every tick.
+fn trace_run(level_map: Grid<u8>, path: &Path) -> Result<RunTrace, String> {
+    let (mut replay, mut sim) = load_replay(level_map, path)?;
+    let mut trace = RunTrace::new();
+    let length = replay.length;
+    play(&mut sim, &mut replay, length, |sim| {
+        trace.record(sim.tick_count(), sim.state_fields());
+    });
+    Ok(trace)
+}
@@ -0,0 +1,64 @@
+// Renders a sound bank without a window or audio device:
+//
+//     cargo run --bin synth -- resources/sounds.json [--out <dir>]
+//
+// Prints every sound's length and a hash of its samples. The synthesizer is deterministic,
+// so the hashes only change when the sound bank or the synthesizer does, which makes them
+// golden values to check changes against. With --out, also writes each sound as a WAV
+// file into <dir> for listening.
+use rust_pack::synth::{self, SAMPLE_RATE};
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+
+const USAGE: &str = "usage: synth <sounds.json> [--out <dir>]";
+
+fn main() -> ExitCode {
+    let mut args = env::args().skip(1);
+    let mut out = None;
+    let mut paths = Vec::new();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--out" => out = args.next().map(PathBuf::from),
+            _ => paths.push(arg),
+        }
+    }
+    let [bank_path] = &paths[..] else {
+        eprintln!("{}", USAGE);
+        return ExitCode::FAILURE;
+    };
+    match run(Path::new(bank_path), out.as_deref()) {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            eprintln!("{}", e);
+            ExitCode::FAILURE
+        }
+    }
+}
+
+fn run(bank_path: &Path, out: Option<&Path>) -> Result<(), String> {
+    let content = fs::read_to_string(bank_path)
+        .map_err(|e| format!("Failed to read sound bank {:?}: {}", bank_path, e))?;
+    let bank = synth::parse_sound_bank(&content)
+        .map_err(|e| format!("Invalid sound bank {:?}: {}", bank_path, e))?;
+    if let Some(dir) = out {
+        fs::create_dir_all(dir).map_err(|e| format!("Failed to create {:?}: {}", dir, e))?;
+    }
+    for name in bank.names() {
+        let samples = bank.render(name).unwrap_or_default();
+        let seconds = samples.len() as f32 / SAMPLE_RATE as f32;
+        println!(
+            "{} {:.3}s {:016x}",
+            name,
+            seconds,
+            synth::samples_hash(&samples)
+        );
+        if let Some(dir) = out {
+            let path = dir.join(format!("{}.wav", name));
+            fs::write(&path, synth::encode_wav(&samples))
+                .map_err(|e| format!("Failed to write {:?}: {}", path, e))?;
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,358 @@
+use ggez::Context;
+use ggez::input::keyboard::KeyCode;
+use std::collections::HashMap;
+use std::fs;
+
+// Something the player or the level designer wants to do, independent of the key it is on.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Action {
+    MoveUp,
+    MoveDown,
+    MoveLeft,
+    MoveRight,
+    Confirm,
+    Back,
+    Pause,
+    ToggleWalls,
+    ToggleScaling,
+    ToggleDebug,
+    VolumeDown,
+    VolumeUp,
+    ToggleMute,
+    ToolEmpty,
+    ToolWall,
+    ToolDot,
+    ToolBigDot,
+    ToolDoor,
+    SymmetryHorizontal,
+    SymmetryVertical,
+    Save,
+    ExportSvg,
+    Clear,
+}
+
+impl Action {
+    const ALL: [Action; 23] = [
+        Action::MoveUp,
+        Action::MoveDown,
+        Action::MoveLeft,
+        Action::MoveRight,
+        Action::Confirm,
+        Action::Back,
+        Action::Pause,
+        Action::ToggleWalls,
+        Action::ToggleScaling,
+        Action::ToggleDebug,
+        Action::VolumeDown,
+        Action::VolumeUp,
+        Action::ToggleMute,
+        Action::ToolEmpty,
+        Action::ToolWall,
+        Action::ToolDot,
+        Action::ToolBigDot,
+        Action::ToolDoor,
+        Action::SymmetryHorizontal,
+        Action::SymmetryVertical,
+        Action::Save,
+        Action::ExportSvg,
+        Action::Clear,
+    ];
+
+    // Name used in the bindings file, e.g. "move_up".
+    pub fn name(self) -> &'static str {
+        match self {
+            Action::MoveUp => "move_up",
+            Action::MoveDown => "move_down",
+            Action::MoveLeft => "move_left",
+            Action::MoveRight => "move_right",
+            Action::Confirm => "confirm",
+            Action::Back => "back",
+            Action::Pause => "pause",
+            Action::ToggleWalls => "toggle_walls",
+            Action::ToggleScaling => "toggle_scaling",
+            Action::ToggleDebug => "toggle_debug",
+            Action::VolumeDown => "volume_down",
+            Action::VolumeUp => "volume_up",
+            Action::ToggleMute => "toggle_mute",
+            Action::ToolEmpty => "tool_empty",
+            Action::ToolWall => "tool_wall",
+            Action::ToolDot => "tool_dot",
+            Action::ToolBigDot => "tool_big_dot",
+            Action::ToolDoor => "tool_door",
+            Action::SymmetryHorizontal => "symmetry_horizontal",
+            Action::SymmetryVertical => "symmetry_vertical",
+            Action::Save => "save",
+            Action::ExportSvg => "export_svg",
+            Action::Clear => "clear",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Action> {
+        Action::ALL.into_iter().find(|a| a.name() == name)
+    }
+}
+
+// Keys that can be named in a bindings file, by their `KeyCode` name ("Up", "W", "Key1").
+const NAMED_KEYS: &[KeyCode] = &[
+    KeyCode::Key1,
+    KeyCode::Key2,
+    KeyCode::Key3,
+    KeyCode::Key4,
+    KeyCode::Key5,
+    KeyCode::Key6,
+    KeyCode::Key7,
+    KeyCode::Key8,
+    KeyCode::Key9,
+    KeyCode::Key0,
+    KeyCode::A,
+    KeyCode::B,
+    KeyCode::C,
+    KeyCode::D,
+    KeyCode::E,
+    KeyCode::F,
+    KeyCode::G,
+    KeyCode::H,
+    KeyCode::I,
+    KeyCode::J,
+    KeyCode::K,
+    KeyCode::L,
+    KeyCode::M,
+    KeyCode::N,
+    KeyCode::O,
+    KeyCode::P,
+    KeyCode::Q,
+    KeyCode::R,
+    KeyCode::S,
+    KeyCode::T,
+    KeyCode::U,
+    KeyCode::V,
+    KeyCode::W,
+    KeyCode::X,
+    KeyCode::Y,
+    KeyCode::Z,
+    KeyCode::Escape,
+    KeyCode::F1,
+    KeyCode::F2,
+    KeyCode::F3,
+    KeyCode::F4,
+    KeyCode::F5,
+    KeyCode::F6,
+    KeyCode::F7,
+    KeyCode::F8,
+    KeyCode::F9,
+    KeyCode::F10,
+    KeyCode::F11,
+    KeyCode::F12,
+    KeyCode::Insert,
+    KeyCode::Home,
+    KeyCode::Delete,
+    KeyCode::End,
+    KeyCode::PageDown,
+    KeyCode::PageUp,
+    KeyCode::Left,
+    KeyCode::Up,
+    KeyCode::Right,
+    KeyCode::Down,
+    KeyCode::Back,
+    KeyCode::Return,
+    KeyCode::Space,
+    KeyCode::Tab,
+    KeyCode::Numpad0,
+    KeyCode::Numpad1,
+    KeyCode::Numpad2,
+    KeyCode::Numpad3,
+    KeyCode::Numpad4,
+    KeyCode::Numpad5,
+    KeyCode::Numpad6,
+    KeyCode::Numpad7,
+    KeyCode::Numpad8,
+    KeyCode::Numpad9,
+    KeyCode::NumpadEnter,
+    KeyCode::Apostrophe,
+    KeyCode::Backslash,
+    KeyCode::Comma,
+    KeyCode::Equals,
+    KeyCode::Grave,
+    KeyCode::LBracket,
+    KeyCode::RBracket,
+    KeyCode::Minus,
+    KeyCode::Period,
+    KeyCode::Semicolon,
+    KeyCode::Slash,
+    KeyCode::LShift,
+    KeyCode::RShift,
+    KeyCode::LControl,
+    KeyCode::RControl,
+    KeyCode::LAlt,
+    KeyCode::RAlt,
+];
+
+// Looks up a key by name, ignoring case.
+pub fn key_from_name(name: &str) -> Option<KeyCode> {
+    NAMED_KEYS
+        .iter()
+        .copied()
+        .find(|key| format!("{:?}", key).eq_ignore_ascii_case(name))
+}
+
+// File in the user config directory that overrides the default bindings, e.g.
+//
+//     {
+//       "move_up": ["Up", "W"],
+//       "move_left": ["Left", "A"],
+//       "pause": ["P", "Space"]
+//     }
+//
+// Every action listed replaces its default keys; actions left out keep them.
+pub const BINDINGS_FILE: &str = "bindings.json";
+
+// Which keys trigger which actions. An action can have any number of keys.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct Bindings {
+    keys: Vec<(Action, Vec<KeyCode>)>, // In the order of the defaults, which decides ties.
+}
+
+impl Bindings {
+    pub fn new(defaults: &[(Action, &[KeyCode])]) -> Bindings {
+        Bindings {
+            keys: defaults
+                .iter()
+                .map(|&(action, keys)| (action, keys.to_vec()))
+                .collect(),
+        }
+    }
+
+    // The action bound to `key`. If several are, the one listed first in the defaults wins.
+    pub fn action(&self, key: KeyCode) -> Option<Action> {
+        self.keys
+            .iter()
+            .find(|(_, keys)| keys.contains(&key))
+            .map(|&(action, _)| action)
+    }
+
+    // The keys bound to `action`, e.g. for on-screen help.
+    pub fn keys(&self, action: Action) -> &[KeyCode] {
+        self.keys
+            .iter()
+            .find(|&&(a, _)| a == action)
+            .map_or(&[], |(_, keys)| keys.as_slice())
+    }
+
+    // Applies a bindings file without touching the filesystem.
+    pub fn apply_overrides(&mut self, content: &str) -> Result<(), String> {
+        let overrides: HashMap<String, Vec<String>> =
+            serde_json::from_str(content).map_err(|e| e.to_string())?;
+        // Sorted so errors and results don't depend on hash order.
+        let mut overrides: Vec<_> = overrides.into_iter().collect();
+        overrides.sort();
+        for (name, key_names) in overrides {
+            let action =
+                Action::from_name(&name).ok_or_else(|| format!("unknown action {:?}", name))?;
+            let keys = key_names
+                .iter()
+                .map(|key| {
+                    key_from_name(key).ok_or_else(|| format!("{}: unknown key {:?}", name, key))
+                })
+                .collect::<Result<Vec<_>, _>>()?;
+            match self.keys.iter_mut().find(|(a, _)| *a == action) {
+                Some((_, bound)) => *bound = keys,
+                None => self.keys.push((action, keys)),
+            }
+        }
+        Ok(())
+    }
+
+    // The defaults, overridden by `bindings.json` in the user config directory if there is
+    // one. A broken file is ignored rather than locking the player out; the second value
+    // says what was wrong with it, for the caller to tell the player.
+    pub fn load_user(
+        ctx: &Context,
+        defaults: &[(Action, &[KeyCode])],
+    ) -> (Bindings, Option<String>) {
+        let bindings = Bindings::new(defaults);
+        let path = ctx.fs.user_config_dir().join(BINDINGS_FILE);
+        let Ok(content) = fs::read_to_string(&path) else {
+            return (bindings, None);
+        };
+        let mut custom = bindings.clone();
+        match custom.apply_overrides(&content) {
+            Ok(()) => (custom, None),
+            Err(e) => (bindings, Some(format!("{:?}: {}", path, e))),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DEFAULTS: &[(Action, &[KeyCode])] = &[
+        (Action::MoveUp, &[KeyCode::Up, KeyCode::W]),
+        (Action::MoveDown, &[KeyCode::Down, KeyCode::S]),
+        (Action::Pause, &[KeyCode::P]),
+    ];
+
+    #[test]
+    fn every_action_has_a_unique_name() {
+        let mut names = Vec::new();
+        for action in Action::ALL {
+            assert_eq!(Action::from_name(action.name()), Some(action));
+            assert!(!names.contains(&action.name()), "{:?}", action.name());
+            names.push(action.name());
+        }
+        assert_eq!(Action::from_name("jump"), None);
+    }
+
+    #[test]
+    fn keys_are_named_ignoring_case() {
+        assert_eq!(key_from_name("Up"), Some(KeyCode::Up));
+        assert_eq!(key_from_name("up"), Some(KeyCode::Up));
+        assert_eq!(key_from_name("NUMPADENTER"), Some(KeyCode::NumpadEnter));
+        assert_eq!(key_from_name("Key1"), Some(KeyCode::Key1));
+        assert_eq!(key_from_name("Hyper"), None);
+    }
+
+    #[test]
+    fn overrides_replace_only_the_listed_actions() {
+        let mut bindings = Bindings::new(DEFAULTS);
+        bindings
+            .apply_overrides(r#"{ "move_up": ["i"], "pause": ["Space", "P"] }"#)
+            .unwrap();
+
+        assert_eq!(bindings.keys(Action::MoveUp), [KeyCode::I]);
+        assert_eq!(bindings.action(KeyCode::W), None);
+        assert_eq!(bindings.keys(Action::MoveDown), [KeyCode::Down, KeyCode::S]);
+        assert_eq!(bindings.action(KeyCode::Space), Some(Action::Pause));
+        assert_eq!(bindings.action(KeyCode::P), Some(Action::Pause));
+    }
+
+    #[test]
+    fn overrides_can_bind_actions_without_defaults() {
+        let mut bindings = Bindings::new(DEFAULTS);
+        bindings
+            .apply_overrides(r#"{ "toggle_mute": ["M"] }"#)
+            .unwrap();
+        assert_eq!(bindings.action(KeyCode::M), Some(Action::ToggleMute));
+    }
+
+    #[test]
+    fn first_default_wins_a_shared_key() {
+        let mut bindings = Bindings::new(DEFAULTS);
+        bindings.apply_overrides(r#"{ "pause": ["Up"] }"#).unwrap();
+        assert_eq!(bindings.action(KeyCode::Up), Some(Action::MoveUp));
+    }
+
+    #[test]
+    fn unknown_names_are_errors() {
+        let mut bindings = Bindings::new(DEFAULTS);
+        assert_eq!(
+            bindings.apply_overrides(r#"{ "jump": ["Space"] }"#),
+            Err(r#"unknown action "jump""#.to_string())
+        );
+        assert_eq!(
+            bindings.apply_overrides(r#"{ "pause": ["P", "Hyper"] }"#),
+            Err(r#"pause: unknown key "Hyper""#.to_string())
+        );
+        assert!(bindings.apply_overrides("[]").is_err());
+    }
+}
@@ -0,0 +1,270 @@
+use crate::ghost::GhostMode;
+use crate::sim::{Simulation, tile_center};
+use crate::{Direction, is_rect_walkable, is_wall_at, tile_at_pixel};
+use std::collections::{BTreeMap, VecDeque};
+use std::sync::mpsc::Receiver;
+
+// A source of player input. `Simulation::step` asks it for the intent of every tick, so
+// the keyboard, a recording, a bot and a network peer all drive the player the same way.
+pub trait Controller {
+    // The direction requested before the next tick of `sim`, or `Direction::Stopped` for
+    // no new request. Called exactly once per tick.
+    fn intent(&mut self, sim: &Simulation) -> Direction;
+}
+
+// A player at this machine. The frontend passes on movement from the keyboard and
+// gamepads with `request`; the latest request is handed to the next tick.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct HumanController {
+    pending: Direction,
+}
+
+impl HumanController {
+    pub fn new() -> HumanController {
+        HumanController::default()
+    }
+
+    pub fn request(&mut self, direction: Direction) {
+        self.pending = direction;
+    }
+
+    // Forgets a request that hasn't been handed on yet, e.g. when a new game starts.
+    pub fn clear(&mut self) {
+        self.pending = Direction::Stopped;
+    }
+}
+
+impl Controller for HumanController {
+    fn intent(&mut self, _sim: &Simulation) -> Direction {
+        std::mem::take(&mut self.pending)
+    }
+}
+
+// Tiles around a dangerous ghost, in steps, that the bot won't walk through.
+const BOT_GHOST_AVOID_TILES: usize = 3;
+
+// A simple computer player: heads for the nearest dot along the shortest path that keeps
+// clear of ghosts that can catch it. It only looks at the simulation, so a run with the
+// bot is as deterministic as one with a recording.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct BotController;
+
+impl BotController {
+    pub fn new() -> BotController {
+        BotController
+    }
+}
+
+impl Controller for BotController {
+    fn intent(&mut self, sim: &Simulation) -> Direction {
+        let player = &sim.player;
+        let tile = tile_at_pixel(player.pos.x, player.pos.y);
+        let offset = tile_center(tile) - player.pos;
+        // Turns only fit within a pixel of a tile center; asking earlier would stop the
+        // player against the corner instead.
+        let centered = offset.x.abs() < 1.0 && offset.y.abs() < 1.0;
+
+        let danger = danger_tiles(sim);
+        // If every way to a dot is covered, run for one anyway rather than freeze.
+        let wanted = bot_path_direction(sim, tile, &danger)
+            .or_else(|| bot_path_direction(sim, tile, &[]))
+            .unwrap_or(Direction::Stopped);
+        let fits = is_rect_walkable(sim.player_step_rect(wanted), &sim.map);
+        if wanted == player.direction.opposite() || (centered && fits) {
+            wanted
+        } else if centered || player.direction == Direction::Stopped {
+            // Off the center line the turn doesn't fit yet, e.g. after stopping short of
+            // a wall: line up with the tile first.
+            let vertical = matches!(wanted, Direction::North | Direction::South);
+            match (vertical, offset.x > 0.0, offset.y > 0.0) {
+                (true, true, _) => Direction::East,
+                (true, false, _) => Direction::West,
+                (false, _, true) => Direction::South,
+                (false, _, false) => Direction::North,
+            }
+        } else {
+            Direction::Stopped
+        }
+    }
+}
+
+// Tiles within `BOT_GHOST_AVOID_TILES` steps of a ghost that is out hunting.
+fn danger_tiles(sim: &Simulation) -> Vec<(isize, isize)> {
+    let mut tiles = Vec::new();
+    for ghost in &sim.ghosts {
+        if ghost.mode != GhostMode::Normal || ghost.in_house {
+            continue;
+        }
+        let mut frontier = vec![ghost.tile()];
+        for _ in 0..=BOT_GHOST_AVOID_TILES {
+            let mut next = Vec::new();
+            for tile in frontier {
+                if tiles.contains(&tile) {
+                    continue;
+                }
+                tiles.push(tile);
+                next.extend(bot_neighbors(sim, tile).map(|(_, neighbor)| neighbor));
+            }
+            frontier = next;
+        }
+    }
+    tiles
+}
+
+// Tiles the bot may walk onto next to `tile`: anything but walls and the ghost house door.
+fn bot_neighbors(
+    sim: &Simulation,
+    (x, y): (isize, isize),
+) -> impl Iterator<Item = (Direction, (isize, isize))> + '_ {
+    sim.map
+        .neighbors(x, y)
+        .filter(|&(_, nx, ny, &cell)| !is_wall_at(nx as isize, ny as isize, &sim.map) && cell != 4)
+        .map(|(direction, nx, ny, _)| (direction, (nx as isize, ny as isize)))
+}
+
+// The first step of the shortest path from `start` to a dot that avoids `blocked`.
+fn bot_path_direction(
+    sim: &Simulation,
+    start: (isize, isize),
+    blocked: &[(isize, isize)],
+) -> Option<Direction> {
+    // Each visited tile remembers the first step taken from `start` to reach it.
+    let mut first_steps: BTreeMap<(isize, isize), Direction> = BTreeMap::new();
+    let mut queue = VecDeque::from([start]);
+    while let Some(tile) = queue.pop_front() {
+        if let Some(&step) = first_steps.get(&tile)
+            && matches!(sim.map.get(tile.0, tile.1), Some(2 | 3))
+        {
+            return Some(step);
+        }
+        for (direction, next) in bot_neighbors(sim, tile) {
+            if next == start || first_steps.contains_key(&next) || blocked.contains(&next) {
+                continue;
+            }
+            let step = first_steps.get(&tile).copied().unwrap_or(direction);
+            first_steps.insert(next, step);
+            queue.push_back(next);
+        }
+    }
+    None
+}
+
+// A player on another machine. The network layer delivers their input as one
+// (tick, intent) message per tick over a channel, so the simulation can only run as far as
+// the messages have arrived; check `is_ready` before each step.
+#[derive(Debug)]
+pub struct RemoteController {
+    messages: Receiver<(u64, Direction)>,
+    received: BTreeMap<u64, Direction>, // Arrived messages for ticks not yet run.
+}
+
+impl RemoteController {
+    pub fn new(messages: Receiver<(u64, Direction)>) -> RemoteController {
+        RemoteController {
+            messages,
+            received: BTreeMap::new(),
+        }
+    }
+
+    // True once the peer's input for the tick after `tick` ticks have run has arrived.
+    pub fn is_ready(&mut self, tick: u64) -> bool {
+        self.receive();
+        self.received.contains_key(&tick)
+    }
+
+    fn receive(&mut self) {
+        while let Ok((tick, direction)) = self.messages.try_recv() {
+            self.received.insert(tick, direction);
+        }
+    }
+}
+
+impl Controller for RemoteController {
+    // A tick whose message hasn't arrived counts as no new request, which desyncs the
+    // peers; callers are expected to wait with `is_ready` instead.
+    fn intent(&mut self, sim: &Simulation) -> Direction {
+        self.receive();
+        let tick = sim.tick_count();
+        self.received.retain(|&t, _| t >= tick);
+        self.received.remove(&tick).unwrap_or(Direction::Stopped)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::load_level_from_string;
+    use crate::replay::play;
+    use std::sync::mpsc;
+
+    const LEVEL: &str = include_str!("../resources/levels/level1.txt");
+
+    fn level_one() -> Simulation {
+        Simulation::new(load_level_from_string(LEVEL), 1)
+    }
+
+    #[test]
+    fn human_request_is_handed_on_once() {
+        let sim = level_one();
+        let mut human = HumanController::new();
+        human.request(Direction::North);
+        human.request(Direction::East);
+        assert_eq!(human.intent(&sim), Direction::East);
+        assert_eq!(human.intent(&sim), Direction::Stopped);
+
+        human.request(Direction::South);
+        human.clear();
+        assert_eq!(human.intent(&sim), Direction::Stopped);
+    }
+
+    #[test]
+    fn bot_runs_are_deterministic() {
+        let run = || {
+            let mut sim = level_one();
+            let mut hashes = Vec::new();
+            play(&mut sim, &mut BotController::new(), 3000, |sim| {
+                hashes.push(sim.state_hash())
+            });
+            (sim.scoreboard.score(), hashes)
+        };
+        let (score, hashes) = run();
+        assert!(score > 0, "the bot ate nothing");
+        assert_eq!(run(), (score, hashes));
+    }
+
+    #[test]
+    fn remote_intents_wait_for_their_tick() {
+        let (sender, receiver) = mpsc::channel();
+        let mut remote = RemoteController::new(receiver);
+        let mut sim = level_one();
+        assert!(!remote.is_ready(0));
+
+        // Messages may arrive ahead of time and out of order.
+        sender.send((1, Direction::East)).unwrap();
+        sender.send((0, Direction::West)).unwrap();
+        assert!(remote.is_ready(0));
+        assert!(remote.is_ready(1));
+        assert!(!remote.is_ready(2));
+
+        assert_eq!(remote.intent(&sim), Direction::West);
+        sim.step(&mut HumanController::new());
+        assert!(!remote.is_ready(0));
+        assert_eq!(remote.intent(&sim), Direction::East);
+        assert!(!remote.is_ready(1));
+    }
+
+    #[test]
+    fn remote_drops_messages_for_ticks_already_run() {
+        let (sender, receiver) = mpsc::channel();
+        let mut remote = RemoteController::new(receiver);
+        let mut sim = level_one();
+        sim.step(&mut HumanController::new());
+        sim.step(&mut HumanController::new());
+
+        sender.send((0, Direction::West)).unwrap();
+        sender.send((2, Direction::North)).unwrap();
+        assert_eq!(remote.intent(&sim), Direction::North);
+        assert!(!remote.is_ready(0));
+        assert_eq!(remote.intent(&sim), Direction::Stopped);
+    }
+}
@@ -0,0 +1,171 @@
+use crate::font::BitmapFont;
+use crate::ghost::{GhostMode, ghost_color};
+use crate::sim::{GhostPhase, Simulation, tile_center};
+use crate::{Direction, Grid, MAZE_OFFSET_Y, TILE_SIZE, is_rect_walkable, is_tile_walkable};
+use ggez::glam::Vec2;
+use ggez::graphics::{Canvas, Color, DrawMode, DrawParam, Mesh, MeshBuilder, Quad, Rect};
+use ggez::{Context, GameResult};
+
+// Tiles of each ghost's predicted path that are drawn.
+const PATH_TILES: usize = 12;
+
+const GRID_COLOR: Color = Color::new(1.0, 1.0, 1.0, 0.15);
+const PLAYER_RECT_COLOR: Color = Color::new(1.0, 1.0, 0.0, 1.0);
+const FREE_COLOR: Color = Color::new(0.0, 1.0, 0.0, 1.0);
+const BLOCKED_COLOR: Color = Color::new(1.0, 0.0, 0.0, 1.0);
+const TEXT_BACKGROUND: Color = Color::new(0.0, 0.0, 0.0, 0.7);
+
+// Draws what the simulation is thinking on top of the game: the tile grid, the player's
+// collision rect with the corners `is_rect_walkable` samples, the rect a requested turn
+// is tested with, each ghost's target tile and predicted path, the mode timers and FPS.
+pub struct DebugOverlay {
+    font: BitmapFont, // The dense label font, so the text covers less of the maze.
+    grid: Mesh,       // Tile grid lines in maze-local pixels.
+}
+
+impl DebugOverlay {
+    pub fn new(ctx: &mut Context, map: &Grid<u8>) -> GameResult<DebugOverlay> {
+        let font = BitmapFont::load(ctx, "/fonts/small.json")?;
+        let (width, height) = (
+            map.width() as f32 * TILE_SIZE,
+            map.height() as f32 * TILE_SIZE,
+        );
+        let mut builder = MeshBuilder::new();
+        for x in 0..=map.width() {
+            let x = x as f32 * TILE_SIZE;
+            builder.line(&[Vec2::new(x, 0.0), Vec2::new(x, height)], 1.0, GRID_COLOR)?;
+        }
+        for y in 0..=map.height() {
+            let y = y as f32 * TILE_SIZE;
+            builder.line(&[Vec2::new(0.0, y), Vec2::new(width, y)], 1.0, GRID_COLOR)?;
+        }
+        let grid = Mesh::from_data(ctx, builder.build());
+        Ok(DebugOverlay { font, grid })
+    }
+
+    // Draws the overlay in screen coordinates, the maze starting at `MAZE_OFFSET_Y`.
+    pub fn draw(
+        &self,
+        ctx: &mut Context,
+        canvas: &mut Canvas,
+        sim: &Simulation,
+        fps: f64,
+    ) -> GameResult {
+        canvas.draw(
+            &self.grid,
+            DrawParam::new().dest(Vec2::new(0.0, MAZE_OFFSET_Y)),
+        );
+
+        let mut builder = MeshBuilder::new();
+        self.build_player(&mut builder, sim)?;
+        self.build_ghosts(&mut builder, sim)?;
+        canvas.draw(&Mesh::from_data(ctx, builder.build()), DrawParam::new());
+        self.draw_corners(canvas, sim);
+        self.draw_text(canvas, sim, fps);
+        Ok(())
+    }
+
+    // The collision rect, plus the rect the requested direction is tested with while the
+    // player isn't moving that way yet: green if the turn fits, red if a wall blocks it.
+    fn build_player(&self, builder: &mut MeshBuilder, sim: &Simulation) -> GameResult {
+        let player = &sim.player;
+        builder.rectangle(
+            DrawMode::stroke(1.0),
+            outline_rect(player.collision_rect()),
+            PLAYER_RECT_COLOR,
+        )?;
+        let desired = player.desired_direction;
+        if desired != Direction::Stopped && desired != player.direction {
+            let rect = sim.player_step_rect(desired);
+            let color = if is_rect_walkable(rect, &sim.map) {
+                FREE_COLOR
+            } else {
+                BLOCKED_COLOR
+            };
+            builder.rectangle(DrawMode::stroke(1.0), outline_rect(rect), color)?;
+        }
+        Ok(())
+    }
+
+    // The four corner pixels `is_rect_walkable` checks, red where they hit a wall.
+    fn draw_corners(&self, canvas: &mut Canvas, sim: &Simulation) {
+        let rect = sim.player.collision_rect();
+        let (right, bottom) = (rect.x + rect.w - 1.0, rect.y + rect.h - 1.0);
+        for (x, y) in [
+            (rect.x, rect.y),
+            (right, rect.y),
+            (rect.x, bottom),
+            (right, bottom),
+        ] {
+            let color = if is_tile_walkable(x, y, &sim.map) {
+                FREE_COLOR
+            } else {
+                BLOCKED_COLOR
+            };
+            canvas.draw(
+                &Quad,
+                DrawParam::new().dest(Vec2::new(x, y).floor()).color(color),
+            );
+        }
+    }
+
+    fn build_ghosts(&self, builder: &mut MeshBuilder, sim: &Simulation) -> GameResult {
+        for (i, ghost) in sim.ghosts.iter().enumerate() {
+            let color = ghost_color(ghost.name);
+            let (x, y) = ghost.target;
+            let target = Rect::new(
+                x as f32 * TILE_SIZE,
+                y as f32 * TILE_SIZE + MAZE_OFFSET_Y,
+                TILE_SIZE,
+                TILE_SIZE,
+            );
+            builder.rectangle(DrawMode::stroke(1.0), outline_rect(target), color)?;
+
+            let mut points = vec![ghost.pos];
+            points.extend(sim.ghost_path(i, PATH_TILES).into_iter().map(tile_center));
+            if points.len() >= 2 {
+                builder.line(&points, 1.0, color)?;
+            }
+        }
+        Ok(())
+    }
+
+    fn draw_text(&self, canvas: &mut Canvas, sim: &Simulation, fps: f64) {
+        let mut lines = vec![format!("FPS {:.0}  TICK {}", fps, sim.tick_count())];
+        lines.push(match (sim.phase(), sim.phase_time_left()) {
+            (GhostPhase::Scatter, Some(left)) => format!("SCATTER {:.1}", left),
+            (GhostPhase::Chase, Some(left)) => format!("CHASE {:.1}", left),
+            (_, None) => "CHASE".to_string(),
+        });
+        for ghost in &sim.ghosts {
+            let state = match ghost.mode {
+                GhostMode::Frightened { time_left } => format!("FRIGHTENED {:.1}", time_left),
+                GhostMode::Eyes => "EYES".to_string(),
+                GhostMode::Normal if ghost.in_house && ghost.release_time > 0.0 => {
+                    format!("HOUSE {:.1}", ghost.release_time)
+                }
+                GhostMode::Normal if ghost.in_house => "LEAVING".to_string(),
+                GhostMode::Normal => "NORMAL".to_string(),
+            };
+            lines.push(format!("{} {}", ghost.name.to_uppercase(), state));
+        }
+        let text = lines.join("\n");
+
+        let dest = Vec2::new(2.0, MAZE_OFFSET_Y + 2.0);
+        let size = self.font.measure(&text, 1);
+        canvas.draw(
+            &Quad,
+            DrawParam::new()
+                .dest(dest - Vec2::ONE)
+                .scale(size + Vec2::splat(2.0))
+                .color(TEXT_BACKGROUND),
+        );
+        self.font.draw(canvas, &text, dest, 1, Color::WHITE);
+    }
+}
+
+// Moves a rect's stroke onto the pixel centers of its outermost pixels, so the outline
+// covers exactly the pixels inside the rect.
+fn outline_rect(rect: Rect) -> Rect {
+    Rect::new(rect.x + 0.5, rect.y + 0.5, rect.w - 1.0, rect.h - 1.0)
+}
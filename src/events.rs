@@ -0,0 +1,38 @@
+use crate::fruit::Fruit;
+use ggez::glam::Vec2;
+
+// Something that happened during a tick, for frontends to react to: sounds, score popups,
+// particles, telemetry. The simulation only reports them; nothing it does depends on
+// whether anyone listens. Positions are screen pixels, like the actors'.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum GameEvent {
+    DotEaten {
+        tile: (isize, isize),
+    },
+    // A power pellet; the ghosts turn frightened along with it.
+    PelletEaten {
+        tile: (isize, isize),
+    },
+    GhostEaten {
+        ghost: &'static str, // Actor name of the ghost, e.g. "inky".
+        pos: Vec2,
+        points: u32,
+    },
+    PlayerCaught {
+        pos: Vec2,
+    },
+    FruitSpawned {
+        fruit: Fruit,
+        pos: Vec2,
+    },
+    FruitEaten {
+        fruit: Fruit,
+        pos: Vec2,
+        points: u32,
+    },
+    // The last dot of `level` was eaten.
+    LevelCleared {
+        level: u32,
+    },
+    ExtraLife,
+}
@@ -0,0 +1,143 @@
+use ggez::glam::Vec2;
+use ggez::graphics::{Canvas, Color, DrawParam, Image, Rect};
+use ggez::{Context, GameError, GameResult};
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::io::Read;
+
+// The metrics file of a bitmap font, e.g. `resources/fonts/arcade.json`:
+//
+//     {
+//       "image": "/fonts/font.png",
+//       "cell_width": 8, "cell_height": 8, "columns": 16,
+//       "chars": " !\"#$%&'()*+,-./0123456789...",
+//       "advance": 8,
+//       "line_height": 8
+//     }
+//
+// The glyph sheet is a grid of `columns` cells per row holding `chars` in order, left to
+// right and top to bottom. Several metrics files can share one sheet, e.g. with a different
+// `advance` for tile-aligned HUD text and for denser editor labels.
+#[derive(Debug, Clone, Deserialize)]
+pub struct FontMetrics {
+    pub image: String,    // Resource path of the glyph sheet.
+    pub cell_width: u32,  // Width of one glyph cell in pixels.
+    pub cell_height: u32, // Height of one glyph cell in pixels.
+    pub columns: u32,     // Cells per row of the sheet.
+    pub chars: String,    // Characters in sheet order.
+    pub advance: u32,     // Horizontal distance between characters in pixels.
+    pub line_height: u32, // Vertical distance between lines in pixels.
+}
+
+// Parses font metrics without touching the filesystem.
+pub fn parse_font_metrics(content: &str) -> Result<FontMetrics, String> {
+    let metrics: FontMetrics = serde_json::from_str(content).map_err(|e| e.to_string())?;
+    if metrics.columns == 0 || metrics.cell_width == 0 || metrics.cell_height == 0 {
+        return Err("cell size and column count must be positive".to_string());
+    }
+    Ok(metrics)
+}
+
+// A fixed-advance bitmap font drawn from a glyph sheet. Text is positioned in whole pixels
+// and scaled by integers only, so glyphs stay pixel-exact with a nearest-neighbor sampler.
+pub struct BitmapFont {
+    image: Image,
+    glyphs: HashMap<char, Rect>, // Normalized UV rect of every glyph on the sheet.
+    cell: Vec2,                  // Glyph cell size in pixels.
+    advance: f32,
+    line_height: f32,
+}
+
+impl BitmapFont {
+    // Loads a metrics file such as "/fonts/arcade.json" and the glyph sheet it points to.
+    pub fn load(ctx: &mut Context, metrics_path: &str) -> GameResult<BitmapFont> {
+        let mut content = String::new();
+        ctx.fs.open(metrics_path)?.read_to_string(&mut content)?;
+        let metrics = parse_font_metrics(&content).map_err(|e| {
+            GameError::ResourceLoadError(format!("Invalid font metrics {}: {}", metrics_path, e))
+        })?;
+        let image = Image::from_path(ctx, &metrics.image)?;
+        Ok(BitmapFont::from_metrics(image, &metrics))
+    }
+
+    // Builds a font from an already loaded glyph sheet.
+    pub fn from_metrics(image: Image, metrics: &FontMetrics) -> BitmapFont {
+        let (sheet_w, sheet_h) = (image.width() as f32, image.height() as f32);
+        let (cell_w, cell_h) = (metrics.cell_width as f32, metrics.cell_height as f32);
+        let glyphs = metrics
+            .chars
+            .chars()
+            .enumerate()
+            .map(|(i, c)| {
+                let column = (i as u32 % metrics.columns) as f32;
+                let row = (i as u32 / metrics.columns) as f32;
+                let rect = Rect::new(
+                    column * cell_w / sheet_w,
+                    row * cell_h / sheet_h,
+                    cell_w / sheet_w,
+                    cell_h / sheet_h,
+                );
+                (c, rect)
+            })
+            .collect();
+        BitmapFont {
+            image,
+            glyphs,
+            cell: Vec2::new(cell_w, cell_h),
+            advance: metrics.advance as f32,
+            line_height: metrics.line_height as f32,
+        }
+    }
+
+    // Pixels between the starts of two lines at scale 1.
+    pub fn line_height(&self) -> f32 {
+        self.line_height
+    }
+
+    // Pixels between the starts of two characters at scale 1.
+    pub fn advance(&self) -> f32 {
+        self.advance
+    }
+
+    // The size of `text` in pixels when drawn at `scale`. Lines are split at '\n'.
+    pub fn measure(&self, text: &str, scale: u32) -> Vec2 {
+        let scale = scale.max(1) as f32;
+        let lines: Vec<&str> = text.split('\n').collect();
+        let longest = lines.iter().map(|l| l.chars().count()).max().unwrap_or(0);
+        if longest == 0 {
+            return Vec2::new(0.0, self.cell.y * scale);
+        }
+        // The last character only takes its cell, not a full advance.
+        let width = (longest - 1) as f32 * self.advance + self.cell.x;
+        let height = (lines.len() - 1) as f32 * self.line_height + self.cell.y;
+        Vec2::new(width, height) * scale
+    }
+
+    // Draws `text` with its top-left corner at `dest`, which is rounded to whole pixels.
+    // Lowercase letters missing from the sheet fall back to uppercase; other missing
+    // characters are left blank.
+    pub fn draw(&self, canvas: &mut Canvas, text: &str, dest: Vec2, scale: u32, color: Color) {
+        let scale = scale.max(1) as f32;
+        let origin = dest.round();
+        for (line_index, line) in text.split('\n').enumerate() {
+            let y = origin.y + line_index as f32 * self.line_height * scale;
+            for (i, c) in line.chars().enumerate() {
+                let glyph = self
+                    .glyphs
+                    .get(&c)
+                    .or_else(|| self.glyphs.get(&c.to_ascii_uppercase()));
+                if let Some(&rect) = glyph {
+                    let x = origin.x + i as f32 * self.advance * scale;
+                    canvas.draw(
+                        &self.image,
+                        DrawParam::new()
+                            .dest(Vec2::new(x, y))
+                            .src(rect)
+                            .scale(Vec2::splat(scale))
+                            .color(color),
+                    );
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,64 @@
+use crate::state_hash::{HashState, StateHasher};
+
+// The bonus fruits, in the order the levels introduce them.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Fruit {
+    Cherry,
+    Strawberry,
+    Orange,
+    Apple,
+    Melon,
+    Galaxian,
+    Bell,
+    Key,
+}
+
+impl Fruit {
+    // The bonus fruit of a level (1-based), following the arcade table.
+    pub fn for_level(level: u32) -> Fruit {
+        match level {
+            0 | 1 => Fruit::Cherry,
+            2 => Fruit::Strawberry,
+            3 | 4 => Fruit::Orange,
+            5 | 6 => Fruit::Apple,
+            7 | 8 => Fruit::Melon,
+            9 | 10 => Fruit::Galaxian,
+            11 | 12 => Fruit::Bell,
+            _ => Fruit::Key,
+        }
+    }
+
+    // Points for eating this fruit.
+    pub fn points(self) -> u32 {
+        match self {
+            Fruit::Cherry => 100,
+            Fruit::Strawberry => 300,
+            Fruit::Orange => 500,
+            Fruit::Apple => 700,
+            Fruit::Melon => 1000,
+            Fruit::Galaxian => 2000,
+            Fruit::Bell => 3000,
+            Fruit::Key => 5000,
+        }
+    }
+
+    // Name of the fruit's sprite in the atlas.
+    pub fn sprite_name(self) -> &'static str {
+        match self {
+            Fruit::Cherry => "fruit_cherry",
+            Fruit::Strawberry => "fruit_strawberry",
+            Fruit::Orange => "fruit_orange",
+            Fruit::Apple => "fruit_apple",
+            Fruit::Melon => "fruit_melon",
+            Fruit::Galaxian => "fruit_galaxian",
+            Fruit::Bell => "fruit_bell",
+            Fruit::Key => "fruit_key",
+        }
+    }
+}
+
+impl HashState for Fruit {
+    fn hash_state(&self, hasher: &mut StateHasher) {
+        (*self as u8).hash_state(hasher);
+    }
+}
@@ -0,0 +1,319 @@
+use crate::Direction;
+use crate::bindings::Action;
+use ggez::event::{Axis, Button};
+use ggez::input::gamepad::GamepadId;
+
+// Number of players controllers can be assigned to.
+pub const PLAYER_SLOTS: usize = 2;
+
+// Stick deflection, out of 1.0, below which the stick counts as centered. Worn sticks rest
+// well off zero, and a small dead zone makes them wander between directions.
+pub const STICK_DEAD_ZONE: f32 = 0.35;
+
+// Hats report whole steps, so any clear deflection counts.
+const HAT_DEAD_ZONE: f32 = 0.5;
+
+// Quantizes a stick position to one of the four directions, by whichever axis is pushed
+// further. `y` is positive upwards, as gilrs reports it. Inside the dead zone, or exactly
+// on a diagonal, the result is `Stopped`.
+pub fn stick_direction(x: f32, y: f32, dead_zone: f32) -> Direction {
+    if x * x + y * y < dead_zone * dead_zone || x.abs() == y.abs() {
+        Direction::Stopped
+    } else if x.abs() > y.abs() {
+        if x > 0.0 {
+            Direction::East
+        } else {
+            Direction::West
+        }
+    } else if y > 0.0 {
+        Direction::North
+    } else {
+        Direction::South
+    }
+}
+
+// The movement action that requests `direction`.
+pub fn move_action(direction: Direction) -> Option<Action> {
+    match direction {
+        Direction::North => Some(Action::MoveUp),
+        Direction::South => Some(Action::MoveDown),
+        Direction::West => Some(Action::MoveLeft),
+        Direction::East => Some(Action::MoveRight),
+        Direction::Stopped => None,
+    }
+}
+
+// Menu actions of the face and menu buttons. The d-pad is handled separately.
+fn button_action(button: Button) -> Option<Action> {
+    match button {
+        Button::South => Some(Action::Confirm),
+        Button::East | Button::Select => Some(Action::Back),
+        Button::Start => Some(Action::Pause),
+        _ => None,
+    }
+}
+
+fn dpad_direction(button: Button) -> Option<Direction> {
+    match button {
+        Button::DPadUp => Some(Direction::North),
+        Button::DPadDown => Some(Direction::South),
+        Button::DPadLeft => Some(Direction::West),
+        Button::DPadRight => Some(Direction::East),
+        _ => None,
+    }
+}
+
+// What one controller is holding.
+#[derive(Debug, Clone, PartialEq, Default)]
+struct PadState {
+    dpad: Vec<Direction>,  // D-pad buttons held, the most recently pressed last.
+    dpad_axes: (f32, f32), // Pads that report the d-pad as a hat instead of buttons.
+    stick: (f32, f32),
+    direction: Direction, // Last direction reported for this pad.
+}
+
+impl PadState {
+    // The direction the pad is pushing: the newest d-pad button, then the hat, then the
+    // stick. The d-pad wins so a resting stick can't override it.
+    fn direction(&self, dead_zone: f32) -> Direction {
+        if let Some(&direction) = self.dpad.last() {
+            return direction;
+        }
+        let (x, y) = self.dpad_axes;
+        match stick_direction(x, y, HAT_DEAD_ZONE) {
+            Direction::Stopped => stick_direction(self.stick.0, self.stick.1, dead_zone),
+            direction => direction,
+        }
+    }
+}
+
+// Turns ggez gamepad events into the same actions the keyboard produces, and keeps track
+// of which controller belongs to which player.
+//
+// Controllers are assigned to the first free player slot when they are first seen, either
+// by `sync` or by their first input, and their slot is freed when they disconnect. ggez
+// doesn't forward connect and disconnect events, so `sync` should be called with the
+// connected pads regularly, e.g. once per update.
+//
+// Movement is reported as an action whenever the direction a pad is pushing changes to a
+// new direction. Letting go reports nothing, like releasing a key: the player keeps going.
+//
+// Pads are told apart by ggez's `GamepadId`, or any other id when there is no real
+// controller to get one from.
+#[derive(Debug, Clone)]
+pub struct GamepadInput<Id = GamepadId> {
+    pub dead_zone: f32,
+    players: [Option<Id>; PLAYER_SLOTS],
+    pads: Vec<(Id, PadState)>, // Every assigned pad.
+}
+
+impl<Id: Copy + Eq> Default for GamepadInput<Id> {
+    fn default() -> GamepadInput<Id> {
+        GamepadInput::new()
+    }
+}
+
+impl<Id: Copy + Eq> GamepadInput<Id> {
+    pub fn new() -> GamepadInput<Id> {
+        GamepadInput {
+            dead_zone: STICK_DEAD_ZONE,
+            players: [None; PLAYER_SLOTS],
+            pads: Vec::new(),
+        }
+    }
+
+    // The player slot `id` is assigned to.
+    pub fn player(&self, id: Id) -> Option<usize> {
+        self.players.iter().position(|&slot| slot == Some(id))
+    }
+
+    // The controller assigned to `player`, if any.
+    pub fn gamepad(&self, player: usize) -> Option<Id> {
+        self.players.get(player).copied().flatten()
+    }
+
+    // Assigns `id` to the first free player slot unless it already has one. Returns its
+    // slot, or None if every slot is taken.
+    pub fn assign(&mut self, id: Id) -> Option<usize> {
+        if let Some(player) = self.player(id) {
+            return Some(player);
+        }
+        let player = self.players.iter().position(Option::is_none)?;
+        self.players[player] = Some(id);
+        self.pads.push((id, PadState::default()));
+        Some(player)
+    }
+
+    // Frees the slot of a disconnected controller, forgetting what it was holding.
+    // Returns the slot it had.
+    pub fn release(&mut self, id: Id) -> Option<usize> {
+        let player = self.player(id)?;
+        self.players[player] = None;
+        self.pads.retain(|&(pad, _)| pad != id);
+        Some(player)
+    }
+
+    // Brings the assignment in line with the controllers currently connected: pads that
+    // went away free their slot, new ones take free slots. Returns the changes as
+    // (player, gamepad, connected), for telling the player.
+    pub fn sync(&mut self, connected: impl IntoIterator<Item = Id>) -> Vec<(usize, Id, bool)> {
+        let connected: Vec<Id> = connected.into_iter().collect();
+        let mut changes = Vec::new();
+        for id in self.players.into_iter().flatten() {
+            if !connected.contains(&id)
+                && let Some(player) = self.release(id)
+            {
+                changes.push((player, id, false));
+            }
+        }
+        for id in connected {
+            if self.player(id).is_none()
+                && let Some(player) = self.assign(id)
+            {
+                changes.push((player, id, true));
+            }
+        }
+        changes
+    }
+
+    // Handles a button press. Returns the action it triggers and the player whose
+    // controller it came from; pads without a slot are ignored.
+    pub fn button_down(&mut self, id: Id, button: Button) -> Option<(usize, Action)> {
+        let player = self.assign(id)?;
+        if let Some(direction) = dpad_direction(button) {
+            let pad = self.pad_mut(id);
+            pad.dpad.retain(|&d| d != direction);
+            pad.dpad.push(direction);
+            return self.direction_changed(id).map(|action| (player, action));
+        }
+        button_action(button).map(|action| (player, action))
+    }
+
+    // Handles a button release. Releasing a d-pad button can hand control back to another
+    // held button or the stick, which is reported like a new press.
+    pub fn button_up(&mut self, id: Id, button: Button) -> Option<(usize, Action)> {
+        let player = self.player(id)?;
+        let direction = dpad_direction(button)?;
+        self.pad_mut(id).dpad.retain(|&d| d != direction);
+        self.direction_changed(id).map(|action| (player, action))
+    }
+
+    // Handles a stick or hat axis moving.
+    pub fn axis(&mut self, id: Id, axis: Axis, value: f32) -> Option<(usize, Action)> {
+        let player = self.assign(id)?;
+        let pad = self.pad_mut(id);
+        match axis {
+            Axis::LeftStickX => pad.stick.0 = value,
+            Axis::LeftStickY => pad.stick.1 = value,
+            Axis::DPadX => pad.dpad_axes.0 = value,
+            Axis::DPadY => pad.dpad_axes.1 = value,
+            _ => return None,
+        }
+        self.direction_changed(id).map(|action| (player, action))
+    }
+
+    // Updates the pad's reported direction, returning the move action if it changed to a
+    // new direction.
+    fn direction_changed(&mut self, id: Id) -> Option<Action> {
+        let dead_zone = self.dead_zone;
+        let pad = self.pad_mut(id);
+        let direction = pad.direction(dead_zone);
+        if direction == pad.direction {
+            return None;
+        }
+        pad.direction = direction;
+        move_action(direction)
+    }
+
+    // Only called for assigned pads, which always have a state.
+    fn pad_mut(&mut self, id: Id) -> &mut PadState {
+        let index = self
+            .pads
+            .iter()
+            .position(|&(pad, _)| pad == id)
+            .expect("assigned gamepad without state");
+        &mut self.pads[index].1
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn stick_inside_the_dead_zone_is_centered() {
+        assert_eq!(stick_direction(0.0, 0.0, 0.35), Direction::Stopped);
+        assert_eq!(stick_direction(0.3, 0.0, 0.35), Direction::Stopped);
+        // Each axis alone is under the dead zone, together they are past it.
+        assert_eq!(stick_direction(0.3, -0.2, 0.35), Direction::East);
+        assert_eq!(stick_direction(0.0, 0.35, 0.35), Direction::North);
+    }
+
+    #[test]
+    fn stick_picks_the_axis_pushed_further() {
+        assert_eq!(stick_direction(0.9, 0.5, 0.35), Direction::East);
+        assert_eq!(stick_direction(-0.9, 0.5, 0.35), Direction::West);
+        assert_eq!(stick_direction(0.5, 0.9, 0.35), Direction::North);
+        assert_eq!(stick_direction(0.5, -0.9, 0.35), Direction::South);
+        assert_eq!(stick_direction(0.7, -0.7, 0.35), Direction::Stopped);
+    }
+
+    #[test]
+    fn sync_reuses_the_slot_of_an_unplugged_pad() {
+        let mut input = GamepadInput::new();
+        assert_eq!(input.sync([10, 20]), [(0, 10, true), (1, 20, true)]);
+        assert_eq!(input.sync([10, 20]), []);
+
+        assert_eq!(input.sync([20]), [(0, 10, false)]);
+        assert_eq!(input.gamepad(0), None);
+        assert_eq!(input.player(20), Some(1));
+
+        assert_eq!(input.sync([20, 30, 40]), [(0, 30, true)]);
+        assert_eq!(input.player(40), None);
+    }
+
+    #[test]
+    fn unplugging_forgets_what_the_pad_held() {
+        let mut input = GamepadInput::new();
+        input.sync([1]);
+        assert_eq!(
+            input.button_down(1, Button::DPadUp),
+            Some((0, Action::MoveUp))
+        );
+        input.sync([]);
+        input.sync([1]);
+        assert_eq!(
+            input.button_down(1, Button::DPadUp),
+            Some((0, Action::MoveUp))
+        );
+    }
+
+    #[test]
+    fn dpad_wins_over_the_stick() {
+        let mut input = GamepadInput::new();
+        assert_eq!(
+            input.axis(1, Axis::LeftStickX, 0.9),
+            Some((0, Action::MoveRight))
+        );
+        assert_eq!(
+            input.button_down(1, Button::DPadDown),
+            Some((0, Action::MoveDown))
+        );
+        assert_eq!(input.axis(1, Axis::LeftStickX, -0.9), None);
+        assert_eq!(
+            input.button_up(1, Button::DPadDown),
+            Some((0, Action::MoveLeft))
+        );
+    }
+
+    #[test]
+    fn letting_go_of_the_stick_reports_nothing() {
+        let mut input = GamepadInput::new();
+        input.axis(1, Axis::LeftStickY, 0.9);
+        assert_eq!(input.axis(1, Axis::LeftStickY, 0.1), None);
+        assert_eq!(
+            input.axis(1, Axis::LeftStickY, 0.9),
+            Some((0, Action::MoveUp))
+        );
+    }
+}
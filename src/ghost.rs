@@ -0,0 +1,69 @@
+use crate::Direction;
+use crate::state_hash::{HashState, StateHasher};
+use ggez::graphics::Color;
+
+// Seconds before the end of frightened mode during which a ghost flashes white.
+pub const FRIGHTENED_FLASH_TIME: f32 = 2.0;
+
+// What a ghost is doing, which also decides how it is drawn.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum GhostMode {
+    Normal,                        // Colored body, eyes looking where it is heading.
+    Frightened { time_left: f32 }, // Blue and edible until `time_left` runs out.
+    Eyes,                          // Eaten; only the eyes travel back to the ghost house.
+}
+
+impl GhostMode {
+    // Counts down frightened mode, turning back to normal when it runs out.
+    pub fn update(&mut self, dt: f32) {
+        if let GhostMode::Frightened { time_left } = self {
+            *time_left -= dt;
+            if *time_left <= 0.0 {
+                *self = GhostMode::Normal;
+            }
+        }
+    }
+
+    // The animation clip for this mode, named as in `resources/animations.json`:
+    // the facing direction ("north"), "frightened", "frightened_flash" or "eyes_<direction>".
+    pub fn animation_state(self, direction: Direction) -> String {
+        // Ghosts always face somewhere; a stopped ghost looks up like the ones in the house.
+        let direction = if direction == Direction::Stopped {
+            Direction::North
+        } else {
+            direction
+        };
+        match self {
+            GhostMode::Normal => direction.name().to_string(),
+            GhostMode::Frightened { time_left } if time_left <= FRIGHTENED_FLASH_TIME => {
+                "frightened_flash".to_string()
+            }
+            GhostMode::Frightened { .. } => "frightened".to_string(),
+            GhostMode::Eyes => format!("eyes_{}", direction.name()),
+        }
+    }
+}
+
+impl HashState for GhostMode {
+    fn hash_state(&self, hasher: &mut StateHasher) {
+        match *self {
+            GhostMode::Normal => 0u8.hash_state(hasher),
+            GhostMode::Frightened { time_left } => {
+                1u8.hash_state(hasher);
+                time_left.hash_state(hasher);
+            }
+            GhostMode::Eyes => 2u8.hash_state(hasher),
+        }
+    }
+}
+
+// The body color of a ghost actor, for text and markers that go with it.
+pub fn ghost_color(name: &str) -> Color {
+    match name {
+        "blinky" => Color::from_rgb(255, 0, 0),
+        "pinky" => Color::from_rgb(255, 184, 255),
+        "inky" => Color::from_rgb(0, 255, 255),
+        "clyde" => Color::from_rgb(255, 184, 82),
+        _ => Color::WHITE,
+    }
+}
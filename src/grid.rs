@@ -0,0 +1,290 @@
+use crate::Direction;
+use std::ops::{Index, IndexMut};
+
+// The four cardinal directions in the order neighbors are visited.
+const NEIGHBOR_DIRECTIONS: [Direction; 4] = [
+    Direction::North,
+    Direction::South,
+    Direction::West,
+    Direction::East,
+];
+
+// A rectangular 2D grid stored as a single row-major `Vec`.
+// Coordinates passed to the checked accessors are signed so callers can probe
+// outside the map (e.g. `x - 1` at the left edge) without casting gymnastics.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Grid<T> {
+    width: usize,
+    height: usize,
+    cells: Vec<T>,
+}
+
+impl<T: Clone> Grid<T> {
+    // Creates a grid of the given size with every cell set to `fill`.
+    pub fn new(width: usize, height: usize, fill: T) -> Self {
+        Grid {
+            width,
+            height,
+            cells: vec![fill; width * height],
+        }
+    }
+}
+
+impl<T> Grid<T> {
+    // Wraps an existing row-major `Vec`. Returns `None` if its length doesn't match the size.
+    pub fn from_vec(width: usize, height: usize, cells: Vec<T>) -> Option<Self> {
+        if cells.len() != width * height {
+            return None;
+        }
+        Some(Grid {
+            width,
+            height,
+            cells,
+        })
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    // Returns true if the coordinates lie inside the grid.
+    pub fn in_bounds(&self, x: isize, y: isize) -> bool {
+        x >= 0 && y >= 0 && (x as usize) < self.width && (y as usize) < self.height
+    }
+
+    // Converts signed coordinates to an index into `cells`, or `None` if out of bounds.
+    fn index_of(&self, x: isize, y: isize) -> Option<usize> {
+        if self.in_bounds(x, y) {
+            Some(y as usize * self.width + x as usize)
+        } else {
+            None
+        }
+    }
+
+    // Bounds-checked lookup.
+    pub fn get(&self, x: isize, y: isize) -> Option<&T> {
+        self.index_of(x, y).map(|i| &self.cells[i])
+    }
+
+    // Bounds-checked mutable lookup.
+    pub fn get_mut(&mut self, x: isize, y: isize) -> Option<&mut T> {
+        self.index_of(x, y).map(|i| &mut self.cells[i])
+    }
+
+    // Sets a cell if it is in bounds. Returns whether the write happened.
+    pub fn set(&mut self, x: isize, y: isize, value: T) -> bool {
+        match self.get_mut(x, y) {
+            Some(cell) => {
+                *cell = value;
+                true
+            }
+            None => false,
+        }
+    }
+
+    // Maps any coordinates back into the grid by wrapping around both edges,
+    // the way the tunnel connects the left and right sides of the maze.
+    pub fn wrap(&self, x: isize, y: isize) -> (usize, usize) {
+        (
+            x.rem_euclid(self.width.max(1) as isize) as usize,
+            y.rem_euclid(self.height.max(1) as isize) as usize,
+        )
+    }
+
+    // Wraparound-aware lookup. Only returns `None` for an empty grid.
+    pub fn get_wrapped(&self, x: isize, y: isize) -> Option<&T> {
+        let (x, y) = self.wrap(x, y);
+        self.get(x as isize, y as isize)
+    }
+
+    // Iterates over the in-bounds N/S/W/E neighbors of a cell as `(direction, x, y, value)`.
+    pub fn neighbors(
+        &self,
+        x: isize,
+        y: isize,
+    ) -> impl Iterator<Item = (Direction, usize, usize, &T)> + '_ {
+        NEIGHBOR_DIRECTIONS
+            .into_iter()
+            .filter_map(move |direction| {
+                let (dx, dy) = direction.delta();
+                let (nx, ny) = (x + dx, y + dy);
+                self.get(nx, ny)
+                    .map(|value| (direction, nx as usize, ny as usize, value))
+            })
+    }
+
+    // Like `neighbors`, but wraps around the edges so every cell has four neighbors.
+    pub fn neighbors_wrapped(
+        &self,
+        x: isize,
+        y: isize,
+    ) -> impl Iterator<Item = (Direction, usize, usize, &T)> + '_ {
+        NEIGHBOR_DIRECTIONS
+            .into_iter()
+            .filter_map(move |direction| {
+                let (dx, dy) = direction.delta();
+                let (nx, ny) = self.wrap(x + dx, y + dy);
+                self.get(nx as isize, ny as isize)
+                    .map(|value| (direction, nx, ny, value))
+            })
+    }
+
+    // A single row as a slice. Panics if `y` is out of bounds.
+    pub fn row(&self, y: usize) -> &[T] {
+        &self.cells[y * self.width..(y + 1) * self.width]
+    }
+
+    // A single row as a mutable slice. Panics if `y` is out of bounds.
+    pub fn row_mut(&mut self, y: usize) -> &mut [T] {
+        &mut self.cells[y * self.width..(y + 1) * self.width]
+    }
+
+    // Iterates over all rows from top to bottom.
+    pub fn rows(&self) -> impl Iterator<Item = &[T]> + '_ {
+        (0..self.height).map(move |y| self.row(y))
+    }
+
+    // Iterates over a single column from top to bottom. Panics if `x` is out of bounds.
+    pub fn column(&self, x: usize) -> impl Iterator<Item = &T> + '_ {
+        assert!(x < self.width, "column {} out of bounds", x);
+        self.cells.iter().skip(x).step_by(self.width)
+    }
+
+    // Iterates over every cell as `(x, y, value)` in row-major order.
+    pub fn iter(&self) -> impl Iterator<Item = (usize, usize, &T)> + '_ {
+        self.cells
+            .iter()
+            .enumerate()
+            .map(move |(i, value)| (i % self.width, i / self.width, value))
+    }
+
+    // The underlying row-major storage.
+    pub fn cells(&self) -> &[T] {
+        &self.cells
+    }
+}
+
+// Panicking `(x, y)` indexing, for coordinates already known to be in bounds.
+impl<T> Index<(usize, usize)> for Grid<T> {
+    type Output = T;
+
+    fn index(&self, (x, y): (usize, usize)) -> &T {
+        assert!(
+            x < self.width && y < self.height,
+            "({}, {}) out of bounds",
+            x,
+            y
+        );
+        &self.cells[y * self.width + x]
+    }
+}
+
+impl<T> IndexMut<(usize, usize)> for Grid<T> {
+    fn index_mut(&mut self, (x, y): (usize, usize)) -> &mut T {
+        assert!(
+            x < self.width && y < self.height,
+            "({}, {}) out of bounds",
+            x,
+            y
+        );
+        &mut self.cells[y * self.width + x]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // 4x3 grid whose cells hold their own index.
+    fn numbered() -> Grid<usize> {
+        Grid::from_vec(4, 3, (0..12).collect()).unwrap()
+    }
+
+    #[test]
+    fn from_vec_checks_the_length() {
+        assert!(Grid::from_vec(4, 3, vec![0; 11]).is_none());
+        assert!(Grid::from_vec(4, 3, vec![0; 13]).is_none());
+        let grid = Grid::from_vec(4, 3, vec![0; 12]).unwrap();
+        assert_eq!((grid.width(), grid.height()), (4, 3));
+        assert!(Grid::<u8>::from_vec(0, 0, Vec::new()).is_some());
+    }
+
+    #[test]
+    fn checked_access_is_row_major() {
+        let mut grid = numbered();
+        assert_eq!(grid.get(1, 2), Some(&9));
+        assert_eq!(grid[(3, 0)], 3);
+        assert_eq!(grid.get(-1, 0), None);
+        assert_eq!(grid.get(4, 0), None);
+        assert_eq!(grid.get(0, 3), None);
+        assert!(grid.set(2, 1, 100));
+        assert!(!grid.set(2, -1, 100));
+        assert_eq!(grid.row(1), [4, 5, 100, 7]);
+    }
+
+    #[test]
+    fn wrap_handles_negative_and_overflowing_coordinates() {
+        let grid = numbered();
+        assert_eq!(grid.wrap(-1, -1), (3, 2));
+        assert_eq!(grid.wrap(4, 3), (0, 0));
+        assert_eq!(grid.wrap(-9, 7), (3, 1));
+        assert_eq!(grid.get_wrapped(-1, 0), Some(&3));
+        assert_eq!(grid.get_wrapped(5, -2), Some(&5));
+        assert_eq!(
+            Grid::<u8>::from_vec(0, 0, Vec::new())
+                .unwrap()
+                .get_wrapped(1, 1),
+            None
+        );
+    }
+
+    #[test]
+    fn neighbors_stop_at_the_edges() {
+        let grid = numbered();
+        let corner: Vec<_> = grid.neighbors(0, 0).collect();
+        assert_eq!(
+            corner,
+            [(Direction::South, 0, 1, &4), (Direction::East, 1, 0, &1)]
+        );
+        assert_eq!(grid.neighbors(1, 1).count(), 4);
+    }
+
+    #[test]
+    fn wrapped_neighbors_cross_the_edges() {
+        let grid = numbered();
+        let corner: Vec<_> = grid.neighbors_wrapped(0, 0).collect();
+        assert_eq!(
+            corner,
+            [
+                (Direction::North, 0, 2, &8),
+                (Direction::South, 0, 1, &4),
+                (Direction::West, 3, 0, &3),
+                (Direction::East, 1, 0, &1),
+            ]
+        );
+    }
+
+    #[test]
+    fn column_runs_top_to_bottom() {
+        let grid = numbered();
+        assert_eq!(grid.column(0).copied().collect::<Vec<_>>(), [0, 4, 8]);
+        assert_eq!(grid.column(3).copied().collect::<Vec<_>>(), [3, 7, 11]);
+    }
+
+    #[test]
+    #[should_panic(expected = "column 4 out of bounds")]
+    fn column_past_the_edge_panics() {
+        let _ = numbered().column(4);
+    }
+
+    #[test]
+    fn iter_visits_every_cell_with_its_coordinates() {
+        let grid = numbered();
+        assert!(grid.iter().all(|(x, y, &value)| value == y * 4 + x));
+        assert_eq!(grid.iter().count(), 12);
+    }
+}
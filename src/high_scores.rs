@@ -0,0 +1,269 @@
+use ggez::{Context, GameResult};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::Path;
+
+// Places in the table.
+pub const HIGH_SCORE_COUNT: usize = 10;
+
+// File in the user data directory holding the table.
+pub const HIGH_SCORES_FILE: &str = "high_scores.json";
+
+// Version written to new high score files; files of other versions count as corrupted.
+pub const HIGH_SCORES_VERSION: u32 = 1;
+
+// Characters initials are made of, in the order the entry screen cycles through them.
+pub const INITIAL_CHARS: &[u8] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZ.";
+pub const INITIALS_LEN: usize = 3;
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct HighScore {
+    pub initials: String, // `INITIALS_LEN` characters of `INITIAL_CHARS`.
+    pub score: u32,
+}
+
+// The best scores ever played on this machine, highest first. Stored as JSON:
+//
+//     {
+//       "version": 1,
+//       "scores": [
+//         { "initials": "ABC", "score": 12340 },
+//         { "initials": "X.Y", "score": 9870 }
+//       ]
+//     }
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct HighScoreTable {
+    entries: Vec<HighScore>, // Sorted by score, at most `HIGH_SCORE_COUNT`.
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct HighScoresDef {
+    version: u32,
+    scores: Vec<HighScore>,
+}
+
+pub fn is_valid_initials(initials: &str) -> bool {
+    initials.len() == INITIALS_LEN && initials.bytes().all(|c| INITIAL_CHARS.contains(&c))
+}
+
+// Parses a high score file without touching the filesystem. Entries that make no sense,
+// e.g. after a hand edit, are dropped rather than failing the whole table.
+pub fn parse_high_scores(content: &str) -> Result<HighScoreTable, String> {
+    let def: HighScoresDef = serde_json::from_str(content).map_err(|e| e.to_string())?;
+    if def.version != HIGH_SCORES_VERSION {
+        return Err(format!(
+            "unsupported version {} (expected {})",
+            def.version, HIGH_SCORES_VERSION
+        ));
+    }
+    let mut table = HighScoreTable::new();
+    for entry in def.scores {
+        if is_valid_initials(&entry.initials) && entry.score > 0 {
+            table.insert(&entry.initials, entry.score);
+        }
+    }
+    Ok(table)
+}
+
+// Serializes a table to the format read by `parse_high_scores`.
+pub fn save_high_scores_to_string(table: &HighScoreTable) -> String {
+    let def = HighScoresDef {
+        version: HIGH_SCORES_VERSION,
+        scores: table.entries.clone(),
+    };
+    serde_json::to_string_pretty(&def).expect("high scores always serialize")
+}
+
+impl HighScoreTable {
+    pub fn new() -> HighScoreTable {
+        HighScoreTable::default()
+    }
+
+    // Loads the table from the user data directory. A missing file is an empty table; a
+    // corrupted one is set aside next to it as "high_scores.json.bad" and replaced by an
+    // empty table, so one bad write doesn't stop the game from starting.
+    pub fn load_user(ctx: &Context) -> HighScoreTable {
+        let path = ctx.fs.user_data_dir().join(HIGH_SCORES_FILE);
+        let Ok(content) = fs::read_to_string(&path) else {
+            return HighScoreTable::new();
+        };
+        match parse_high_scores(&content) {
+            Ok(table) => table,
+            Err(e) => {
+                let backup = path.with_extension("json.bad");
+                eprintln!(
+                    "High scores in {:?} are corrupted ({}), moving them to {:?}",
+                    path, e, backup
+                );
+                if let Err(e) = fs::rename(&path, &backup) {
+                    eprintln!("Failed to move {:?}: {}", path, e);
+                }
+                HighScoreTable::new()
+            }
+        }
+    }
+
+    pub fn save_user(&self, ctx: &Context) -> GameResult {
+        self.write(&ctx.fs.user_data_dir().join(HIGH_SCORES_FILE))
+    }
+
+    // Writes to a temporary file first and then renames it over the old one, so a crash
+    // halfway leaves the previous table intact.
+    pub fn write(&self, path: &Path) -> GameResult {
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        let temp = path.with_extension("json.tmp");
+        fs::write(&temp, save_high_scores_to_string(self))?;
+        fs::rename(&temp, path)?;
+        Ok(())
+    }
+
+    pub fn entries(&self) -> &[HighScore] {
+        &self.entries
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    // The best score, or 0 for an empty table.
+    pub fn top_score(&self) -> u32 {
+        self.entries.first().map_or(0, |entry| entry.score)
+    }
+
+    // Whether `score` earns a place in the table.
+    pub fn qualifies(&self, score: u32) -> bool {
+        score > 0
+            && (self.entries.len() < HIGH_SCORE_COUNT
+                || self.entries.last().is_some_and(|last| score > last.score))
+    }
+
+    // Adds a score below any equal ones already in the table and returns its place, or
+    // None if it didn't make the table.
+    pub fn insert(&mut self, initials: &str, score: u32) -> Option<usize> {
+        if !self.qualifies(score) {
+            return None;
+        }
+        let place = self.entries.partition_point(|entry| entry.score >= score);
+        self.entries.insert(
+            place,
+            HighScore {
+                initials: initials.to_string(),
+                score,
+            },
+        );
+        self.entries.truncate(HIGH_SCORE_COUNT);
+        Some(place)
+    }
+}
+
+// "1ST", "2ND", ... for the place at `index`.
+pub fn place_name(index: usize) -> String {
+    let place = index + 1;
+    let suffix = match (place % 10, place % 100) {
+        (_, 11..=13) => "TH",
+        (1, _) => "ST",
+        (2, _) => "ND",
+        (3, _) => "RD",
+        _ => "TH",
+    };
+    format!("{}{}", place, suffix)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn table(scores: &[(&str, u32)]) -> HighScoreTable {
+        let mut table = HighScoreTable::new();
+        for &(initials, score) in scores {
+            table.insert(initials, score);
+        }
+        table
+    }
+
+    fn initials(table: &HighScoreTable) -> Vec<&str> {
+        table
+            .entries()
+            .iter()
+            .map(|e| e.initials.as_str())
+            .collect()
+    }
+
+    #[test]
+    fn table_round_trips() {
+        let table = table(&[("ABC", 12340), ("X.Y", 9870), ("ZZZ", 9870)]);
+        let parsed = parse_high_scores(&save_high_scores_to_string(&table)).unwrap();
+        assert_eq!(parsed, table);
+        assert_eq!(initials(&parsed), ["ABC", "X.Y", "ZZZ"]);
+    }
+
+    #[test]
+    fn other_version_is_rejected() {
+        let error = parse_high_scores(r#"{ "version": 2, "scores": [] }"#).unwrap_err();
+        assert_eq!(error, "unsupported version 2 (expected 1)");
+        assert!(parse_high_scores("{ \"version\": 1, \"scores\": [").is_err());
+    }
+
+    #[test]
+    fn invalid_entries_are_dropped() {
+        let parsed = parse_high_scores(
+            r#"{ "version": 1, "scores": [
+                { "initials": "ABC", "score": 500 },
+                { "initials": "abc", "score": 400 },
+                { "initials": "ABCD", "score": 300 },
+                { "initials": "AB", "score": 200 },
+                { "initials": "XYZ", "score": 0 },
+                { "initials": "A.Z", "score": 100 }
+            ] }"#,
+        )
+        .unwrap();
+        assert_eq!(initials(&parsed), ["ABC", "A.Z"]);
+    }
+
+    #[test]
+    fn ties_go_below_equal_scores() {
+        let mut table = table(&[("AAA", 300), ("BBB", 200), ("CCC", 100)]);
+        assert_eq!(table.insert("DDD", 200), Some(2));
+        assert_eq!(initials(&table), ["AAA", "BBB", "DDD", "CCC"]);
+        assert_eq!(table.top_score(), 300);
+    }
+
+    #[test]
+    fn table_keeps_the_best_scores() {
+        let mut table = HighScoreTable::new();
+        for score in 1..=HIGH_SCORE_COUNT as u32 {
+            table.insert("AAA", score * 10);
+        }
+        assert_eq!(table.entries().len(), HIGH_SCORE_COUNT);
+        assert!(!table.qualifies(10));
+        assert!(!table.qualifies(0));
+        assert_eq!(table.insert("BBB", 10), None);
+
+        assert_eq!(table.insert("BBB", 55), Some(5));
+        assert_eq!(table.entries().len(), HIGH_SCORE_COUNT);
+        assert_eq!(table.entries().last().unwrap().score, 20);
+    }
+
+    #[test]
+    fn zero_never_qualifies() {
+        assert!(!HighScoreTable::new().qualifies(0));
+        assert!(HighScoreTable::new().qualifies(10));
+    }
+
+    #[test]
+    fn place_names_use_english_ordinals() {
+        let names: Vec<_> = [0, 1, 2, 3, 10, 11, 12, 20, 21, 100, 110]
+            .into_iter()
+            .map(place_name)
+            .collect();
+        assert_eq!(
+            names,
+            [
+                "1ST", "2ND", "3RD", "4TH", "11TH", "12TH", "13TH", "21ST", "22ND", "101ST",
+                "111TH"
+            ]
+        );
+    }
+}
@@ -0,0 +1,105 @@
+use crate::TILE_SIZE;
+use crate::atlas::SpriteAtlas;
+use crate::font::BitmapFont;
+use crate::score::Scoreboard;
+use ggez::glam::Vec2;
+use ggez::graphics::{Canvas, Color, DrawParam};
+
+// The arcade HUD on the 28x36 tile screen: labels and scores in the three rows above
+// the maze, remaining lives and the fruit history in the two rows below it.
+const SCORE_LABEL_ROW: f32 = 0.0;
+const SCORE_ROW: f32 = 1.0;
+const BOTTOM_ROW: f32 = 34.0;
+
+// Columns of the last digit of each score, so scores grow to the left like on the arcade.
+const PLAYER_SCORE_END_COLUMNS: [f32; 2] = [6.0, 25.0];
+const HIGH_SCORE_END_COLUMN: f32 = 16.0;
+
+// Number of fruits shown in the bottom-right corner.
+pub const HUD_FRUIT_COUNT: usize = 7;
+
+// Draws text with its top-left corner at a tile position. Meant for a font whose advance
+// is one tile (`fonts/arcade.json`), so every character lands on the tile grid.
+pub fn draw_text(
+    canvas: &mut Canvas,
+    font: &BitmapFont,
+    text: &str,
+    column: f32,
+    row: f32,
+    color: Color,
+) {
+    let dest = Vec2::new(column * TILE_SIZE, row * TILE_SIZE);
+    font.draw(canvas, text, dest, 1, color);
+}
+
+// Like `draw_text`, but with the last character at `end_column`.
+pub fn draw_text_right(
+    canvas: &mut Canvas,
+    font: &BitmapFont,
+    text: &str,
+    end_column: f32,
+    row: f32,
+    color: Color,
+) {
+    let column = end_column + 1.0 - text.chars().count() as f32;
+    draw_text(canvas, font, text, column, row, color);
+}
+
+// Scores are shown with at least two digits, so a fresh game reads "00".
+fn format_score(score: u32) -> String {
+    format!("{:02}", score)
+}
+
+// Draws the whole HUD. `blink_on` toggles the label of the player in play, which
+// blinks during a game; pass `true` to keep it steady.
+pub fn draw_hud(
+    canvas: &mut Canvas,
+    atlas: &SpriteAtlas,
+    font: &BitmapFont,
+    scoreboard: &Scoreboard,
+    blink_on: bool,
+) {
+    let white = Color::WHITE;
+
+    let players = PLAYER_SCORE_END_COLUMNS.iter().zip(scoreboard.scores);
+    for (player, (&end, score)) in players.enumerate().take(scoreboard.player_count) {
+        if blink_on || player != scoreboard.current_player {
+            let label = format!("{}UP", player + 1);
+            draw_text_right(canvas, font, &label, end - 1.0, SCORE_LABEL_ROW, white);
+        }
+        draw_text_right(canvas, font, &format_score(score), end, SCORE_ROW, white);
+    }
+
+    draw_text(canvas, font, "HIGH SCORE", 9.0, SCORE_LABEL_ROW, white);
+    if scoreboard.high_score > 0 {
+        draw_text_right(
+            canvas,
+            font,
+            &format_score(scoreboard.high_score),
+            HIGH_SCORE_END_COLUMN,
+            SCORE_ROW,
+            white,
+        );
+    }
+
+    // One icon per life still in reserve; the life in play isn't shown.
+    if let Some(life_rect) = atlas.get("player_west_half") {
+        for i in 0..scoreboard.lives.saturating_sub(1) {
+            let dest = Vec2::new((2.0 + 2.0 * i as f32) * TILE_SIZE, BOTTOM_ROW * TILE_SIZE);
+            canvas.draw(atlas.image(), DrawParam::new().dest(dest).src(life_rect));
+        }
+    }
+
+    // The current level's fruit sits at the right edge, older ones to its left.
+    for (i, fruit) in scoreboard
+        .recent_fruits(HUD_FRUIT_COUNT)
+        .iter()
+        .rev()
+        .enumerate()
+    {
+        if let Some(rect) = atlas.get(fruit.sprite_name()) {
+            let dest = Vec2::new((24.0 - 2.0 * i as f32) * TILE_SIZE, BOTTOM_ROW * TILE_SIZE);
+            canvas.draw(atlas.image(), DrawParam::new().dest(dest).src(rect));
+        }
+    }
+}
@@ -1,44 +1,150 @@
-use ggez::glam::Vec2;
-use ggez::graphics::{self, Rect};
+use ggez::graphics::Rect;
+
+pub mod animation;
+pub mod atlas;
+pub mod audio;
+pub mod autotile;
+pub mod bindings;
+pub mod controller;
+pub mod debug_overlay;
+pub mod events;
+pub mod font;
+pub mod fruit;
+pub mod gamepad;
+pub mod ghost;
+pub mod grid;
+pub mod high_scores;
+pub mod hud;
+pub mod outline;
+pub mod render;
+pub mod replay;
+pub mod rng;
+pub mod scene;
+pub mod score;
+pub mod sim;
+pub mod state_hash;
+pub mod synth;
+pub mod tileset;
+pub mod viewport;
+
+pub use animation::{AnimationLibrary, Animator, Clip, Frame};
+pub use atlas::SpriteAtlas;
+pub use audio::{AudioPlayer, Sound};
+pub use autotile::{TileScheme, WallAutotiler, WallKind, WallTile};
+pub use bindings::{Action, Bindings};
+pub use controller::{BotController, Controller, HumanController, RemoteController};
+pub use debug_overlay::DebugOverlay;
+pub use events::GameEvent;
+pub use font::BitmapFont;
+pub use fruit::Fruit;
+pub use gamepad::GamepadInput;
+pub use ghost::GhostMode;
+pub use grid::Grid;
+pub use outline::Polyline;
+pub use render::MazeRenderer;
+pub use replay::{Recorder, Replay};
+pub use rng::Rng;
+pub use scene::{Scene, SceneStack, Transition};
+pub use score::Scoreboard;
+pub use sim::Simulation;
+pub use tileset::WallTileset;
+pub use viewport::{ScaleMode, Viewport};
 
 // --- Constants ---
 pub const TILE_SIZE: f32 = 8.0; // Size of a single tile in pixels.
-pub const MAZE_OFFSET_Y: f32 = TILE_SIZE * 5.0; // Vertical offset for the maze to make space for UI elements.
-pub const WALL_CODE_OFFSET: u8 = 100; // Offset added to wall mask to distinguish wall types in `display_map`.
+pub const MAZE_OFFSET_Y: f32 = TILE_SIZE * 3.0; // Vertical offset for the maze, leaving the top HUD rows free.
 pub const PLAYER_SPEED: f32 = 40.0; // Player movement speed in pixels per second.
 
+// Spawn points in tile units (x, y), measured to the actor's center from the maze's top-left corner.
+pub const PLAYER_START: (f32, f32) = (13.5, 23.5);
+// Ghost spawn points on tile centers, with each ghost's animation actor name and initial facing.
+// Blinky starts on the ghost house door, the others inside the house.
+pub const GHOST_STARTS: [(&str, (f32, f32), Direction); 4] = [
+    ("blinky", (13.5, 11.5), Direction::West),
+    ("pinky", (13.5, 14.5), Direction::South),
+    ("inky", (11.5, 14.5), Direction::North),
+    ("clyde", (15.5, 14.5), Direction::North),
+];
+
 // Represents the cardinal directions and a stopped state for movement.
-#[derive(Debug, Clone, Copy, PartialEq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
 pub enum Direction {
     North,
     East,
     South,
     West,
+    #[default]
     Stopped,
 }
 
-// Checks if a tile at given map coordinates is a wall. Handles out-of-bounds coordinates by treating them as walls.
-pub fn is_wall_at(x: isize, y: isize, map: &Vec<Vec<u8>>) -> bool {
-    if y < 0 || (y as usize) >= map.len() {
-        return true;
+impl Direction {
+    // The (x, y) map step for one tile in this direction. Y grows downwards.
+    pub fn delta(self) -> (isize, isize) {
+        match self {
+            Direction::North => (0, -1),
+            Direction::East => (1, 0),
+            Direction::South => (0, 1),
+            Direction::West => (-1, 0),
+            Direction::Stopped => (0, 0),
+        }
     }
-    let row = &map[y as usize];
-    if x < 0 || (x as usize) >= row.len() {
-        return true;
+
+    // The direction straight back; `Stopped` stays stopped.
+    pub fn opposite(self) -> Direction {
+        match self {
+            Direction::North => Direction::South,
+            Direction::East => Direction::West,
+            Direction::South => Direction::North,
+            Direction::West => Direction::East,
+            Direction::Stopped => Direction::Stopped,
+        }
     }
 
-    row[x as usize] == 1
+    // Lower-case name, as used for animation states (e.g. "north").
+    pub fn name(self) -> &'static str {
+        match self {
+            Direction::North => "north",
+            Direction::East => "east",
+            Direction::South => "south",
+            Direction::West => "west",
+            Direction::Stopped => "stopped",
+        }
+    }
+
+    // The direction with the given `name`, e.g. when reading recorded inputs.
+    pub fn from_name(name: &str) -> Option<Direction> {
+        [
+            Direction::North,
+            Direction::East,
+            Direction::South,
+            Direction::West,
+            Direction::Stopped,
+        ]
+        .into_iter()
+        .find(|d| d.name() == name)
+    }
 }
 
-// Converts pixel coordinates to map coordinates and checks if the corresponding tile is walkable.
-pub fn is_tile_walkable(pixel_x: f32, pixel_y: f32, map: &Vec<Vec<u8>>) -> bool {
+// Checks if a tile at given map coordinates is a wall. Handles out-of-bounds coordinates by treating them as walls.
+pub fn is_wall_at(x: isize, y: isize, map: &Grid<u8>) -> bool {
+    map.get(x, y).is_none_or(|&tile| tile == 1)
+}
+
+// Converts screen pixel coordinates (maze offset included) to map coordinates.
+pub fn tile_at_pixel(pixel_x: f32, pixel_y: f32) -> (isize, isize) {
     let map_y = ((pixel_y - MAZE_OFFSET_Y) / TILE_SIZE) as isize;
     let map_x = (pixel_x / TILE_SIZE) as isize;
+    (map_x, map_y)
+}
+
+// Converts pixel coordinates to map coordinates and checks if the corresponding tile is walkable.
+pub fn is_tile_walkable(pixel_x: f32, pixel_y: f32, map: &Grid<u8>) -> bool {
+    let (map_x, map_y) = tile_at_pixel(pixel_x, pixel_y);
     !is_wall_at(map_x, map_y, map) // Returns true if the tile is NOT a wall.
 }
 
 // Checks if all four corners of a given Rect are on walkable tiles, used for collision detection.
-pub fn is_rect_walkable(rect: Rect, level_map: &Vec<Vec<u8>>) -> bool {
+pub fn is_rect_walkable(rect: Rect, level_map: &Grid<u8>) -> bool {
     let right_edge = rect.x + rect.w - 1.0;
     let bottom_edge = rect.y + rect.h - 1.0;
 
@@ -59,31 +165,33 @@ pub fn is_rect_walkable(rect: Rect, level_map: &Vec<Vec<u8>>) -> bool {
     true // All corners are walkable.
 }
 
-pub fn load_level_from_string(content: &str) -> Vec<Vec<u8>> {
+// Parses a level from its text form. Short lines are padded with empty space so the grid is rectangular.
+pub fn load_level_from_string(content: &str) -> Grid<u8> {
     let lines: Vec<_> = content.lines().collect();
-    let max_width = lines.iter().map(|s| s.len()).max().unwrap_or(0);
+    let width = lines.iter().map(|s| s.len()).max().unwrap_or(0);
 
-    lines
-        .into_iter()
-        .map(|line| {
-            let mut row: Vec<u8> = line
-                .chars()
-                .map(|c| match c {
-                    '#' => 1,
-                    '.' => 2,
-                    'o' => 3,
-                    '-' => 4,
-                    _ => 0,
-                })
-                .collect();
-            row.resize(max_width, 0); // Pad with empty space
-            row
-        })
-        .collect()
+    let mut cells = Vec::with_capacity(width * lines.len());
+    for line in &lines {
+        let mut row: Vec<u8> = line
+            .chars()
+            .map(|c| match c {
+                '#' => 1,
+                '.' => 2,
+                'o' => 3,
+                '-' => 4,
+                _ => 0,
+            })
+            .collect();
+        row.resize(width, 0); // Pad with empty space
+        cells.extend(row);
+    }
+
+    Grid::from_vec(width, lines.len(), cells).expect("every row is padded to the same width")
 }
 
-pub fn save_level_to_string(map: &Vec<Vec<u8>>) -> String {
-    map.iter()
+// Serializes a level back to the text form read by `load_level_from_string`.
+pub fn save_level_to_string(map: &Grid<u8>) -> String {
+    map.rows()
         .map(|row| {
             row.iter()
                 .map(|&cell| match cell {
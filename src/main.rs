@@ -1,8 +1,12 @@
-use ggez::event::{self, EventHandler};
+use ggez::event::{self, Axis, Button, EventHandler};
 use ggez::glam::Vec2;
-use ggez::graphics::{self, Color, DrawParam, Image, Rect};
+use ggez::graphics::{self, Color, DrawParam, Rect};
+use ggez::input::gamepad::GamepadId;
 use ggez::input::keyboard::{KeyCode, KeyInput};
-use ggez::{Context, ContextBuilder, GameResult};
+use ggez::{Context, ContextBuilder, GameError, GameResult};
+use rust_pack::audio::{SoundCues, VOLUME_STEP};
+use rust_pack::high_scores::{HighScoreTable, INITIAL_CHARS, INITIALS_LEN, place_name};
+use rust_pack::sim::{FRUIT_TILE, TICK_RATE, TICK_SECONDS, tile_center};
 use rust_pack::*;
 use std::env;
 use std::io::Read;
@@ -22,7 +26,12 @@ fn main() -> GameResult {
     // Create a new game context and event loop with specified window settings.
     let (mut ctx, event_loop) = ContextBuilder::new("rust_pac", "Sir Marshall")
         .window_setup(ggez::conf::WindowSetup::default().title("RUST PAC v1.0"))
-        .window_mode(ggez::conf::WindowMode::default().dimensions(672.0, 864.0))
+        .window_mode(
+            ggez::conf::WindowMode::default()
+                .dimensions(672.0, 864.0)
+                .resizable(true)
+                .min_dimensions(SCREEN_WIDTH, SCREEN_HEIGHT),
+        )
         .add_resource_path(resource_dir)
         .build()?;
 
@@ -35,69 +44,124 @@ fn main() -> GameResult {
 
 // --- Game Structures ---
 
-// Represents the player character.
-struct Player {
-    pos: Vec2,               // Current position of the player (center of sprite).
-    direction: Direction,    // Current actual movement direction.
-    desired_direction: Direction, // Direction input by the player, used for turning logic.
-    sprite_rects: Vec<Rect>, // Normalized UV coordinates for player animation frames.
+// How the maze walls are drawn.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum WallStyle {
+    Tiles,  // Autotiled bitmap walls from the atlas.
+    Vector, // Rounded outlines traced from the level grid.
 }
 
-// Holds all the game's state, assets, and game logic data.
-struct GameState {
-    wall_images: Vec<Image>, // Loaded images for different wall configurations.
-    sprite_sheet: Image,     // Single sprite sheet for player and dots.
-    display_map: Vec<Vec<u8>>, // Map used for drawing, includes pre-calculated wall configurations.
-    level_map: Vec<Vec<u8>>, // Base map for game logic (walls, dots, empty spaces).
-    small_dot_rect: Rect,    // UV coordinates for the small dot sprite.
-    big_dot_rect: Rect,      // UV coordinates for the big dot (power pellet) sprite.
-    player: Player,          // The player character.
+// Everything the scenes share: assets, the running simulation and how it is drawn.
+struct World {
+    atlas: SpriteAtlas, // Texture atlas holding every sprite (walls, player, dots, ghosts).
+    font: BitmapFont,   // Tile-aligned arcade font for the HUD and messages.
+    animations: AnimationLibrary, // Clips of every actor, used to rebuild animators for a new game.
+    wall_tileset: WallTileset, // Atlas rects for the different wall configurations.
+    level_map: Grid<u8>, // The level as loaded; every game starts from a copy.
+    walls: WallAutotiler, // Wall masks used to pick the wall image for each wall tile.
+    wall_style: WallStyle, // Current wall renderer, toggled with V.
+    wall_mesh: graphics::Mesh, // Vector wall outlines, built once from `level_map`.
+    maze_renderer: MazeRenderer, // Cached wall layer and dot instances.
+    small_dot_rect: Rect, // UV coordinates for the small dot sprite.
+    big_dot_rect: Rect, // UV coordinates for the big dot (power pellet) sprite.
+    sim: Simulation,    // Game rules: maze, actors and score.
+    dots_shown: usize,  // Dots left when the dot instances were last rebuilt.
+    player_animation: Animator, // Chomp clips per facing direction, plus the death clip.
+    ghost_animations: Vec<Animator>, // One per ghost of `sim`, in the same order.
+    bindings: Bindings, // Keys for each action, defaults overridden by the user's bindings file.
+    controller: Recorder<HumanController>, // Movement from the keyboard and gamepads, recorded as it is handed on.
+    demo: Replay,                          // Recorded game of the attract-mode demo.
+    popups: Vec<ScorePopup>,               // Points of recently eaten ghosts and fruits.
+    animation_events: Vec<String>,         // Frame events of the last tick, each naming a sound.
+    audio: AudioPlayer, // Sound effects and loops, with the volume keys' settings.
+    sound_cues: SoundCues, // Which sound each event gets.
+    high_scores: HighScoreTable, // Top ten of every game played here, saved in the user data directory.
+    ticks: u64,                  // Ticks since start, for blinking text.
+    notice: Option<Notice>, // Status line of the last setting changed or file saved, while it is up.
 }
 
-// --- GameState Implementation ---
+// Logical screen size in pixels, the original Pac-Man resolution. The window shows it scaled.
+const SCREEN_WIDTH: f32 = 224.0;
+const SCREEN_HEIGHT: f32 = 288.0;
 
-impl GameState {
-    // Creates a new GameState, loading assets and setting up initial game elements.
-    fn new(ctx: &mut Context) -> GameResult<GameState> {
-        // Load wall images (16 variations based on surrounding walls).
-        let mut wall_images = Vec::with_capacity(16);
-        for i in 0..16 {
-            let path = format!("/walls/wall_{}.png", i);
-            wall_images.push(Image::from_path(ctx, &path)?);
-        }
-
-        // Load the main sprite sheet containing player and dot sprites.
-        let sprite_sheet = Image::from_path(ctx, "/player.png")?;
-
-        // Define normalized dimensions for sprites on the sheet (sheet has 6 columns).
-        let sprite_width_normalized = 1.0 / 6.0;
-        let sprite_height_normalized = 1.0;
-
-        // Extract UV coordinates for player animation frames (first 4 sprites).
-        let player_rects = (0..4)
-            .map(|i| {
-                Rect::new(
-                    i as f32 * sprite_width_normalized,
-                    0.0,
-                    sprite_width_normalized,
-                    sprite_height_normalized,
-                )
-            })
-            .collect();
-
-        // Extract UV coordinates for the small and big dot sprites.
-        let small_dot_rect = Rect::new(
-            4.0 * sprite_width_normalized,
-            0.0,
-            sprite_width_normalized,
-            sprite_height_normalized,
-        );
-        let big_dot_rect = Rect::new(
-            5.0 * sprite_width_normalized,
-            0.0,
-            sprite_width_normalized,
-            sprite_height_normalized,
-        );
+// Default keys of every action the game uses; `bindings.json` in the user config
+// directory can rebind them.
+const GAME_BINDINGS: &[(Action, &[KeyCode])] = &[
+    (Action::MoveUp, &[KeyCode::Up, KeyCode::W]),
+    (Action::MoveDown, &[KeyCode::Down, KeyCode::S]),
+    (Action::MoveLeft, &[KeyCode::Left, KeyCode::A]),
+    (Action::MoveRight, &[KeyCode::Right, KeyCode::D]),
+    (Action::Confirm, &[KeyCode::Return, KeyCode::NumpadEnter]),
+    (Action::Back, &[KeyCode::Escape]),
+    (Action::Pause, &[KeyCode::P]),
+    (Action::ToggleWalls, &[KeyCode::V]),
+    (Action::ToggleScaling, &[KeyCode::F2]),
+    (Action::ToggleDebug, &[KeyCode::F3]),
+    (Action::VolumeDown, &[KeyCode::Minus]),
+    (Action::VolumeUp, &[KeyCode::Equals]),
+    (Action::ToggleMute, &[KeyCode::M]),
+];
+
+// Points shown where a ghost or fruit was eaten, for a while after.
+struct ScorePopup {
+    points: u32,
+    pos: Vec2, // Center in screen pixels.
+    color: Color,
+    time_left: f32,
+}
+
+// Seconds a score popup stays up.
+const POPUP_SECONDS: f32 = 1.0;
+const GHOST_POINTS_COLOR: Color = Color::new(0.0, 1.0, 1.0, 1.0);
+const FRUIT_POINTS_COLOR: Color = Color::new(1.0, 0.72, 1.0, 1.0);
+
+// A short status line shown over every screen for a moment, e.g. the volume after a
+// volume key.
+struct Notice {
+    text: String,
+    time_left: f32,
+}
+
+// Seconds a notice stays up, and the row it is shown on: the empty one above the maze.
+const NOTICE_SECONDS: f32 = 1.5;
+const NOTICE_ROW: f32 = 2.0;
+
+// Replay of the last game, overwritten when the next one ends.
+const LAST_REPLAY_FILE: &str = "last_replay.json";
+
+// Seconds between toggles of the blinking "1UP" label.
+const HUD_BLINK_INTERVAL: f32 = 0.25;
+
+// Colors of messages on the playfield.
+const READY_COLOR: Color = Color::new(1.0, 1.0, 0.0, 1.0);
+const PLAYER_ONE_COLOR: Color = Color::new(0.0, 1.0, 1.0, 1.0);
+const GAME_OVER_COLOR: Color = Color::new(1.0, 0.0, 0.0, 1.0);
+
+// Screen rows of the messages shown below and above the ghost house.
+const MESSAGE_ROW: f32 = 20.0;
+const PLAYER_ONE_ROW: f32 = 14.0;
+
+// Shown after the last life and during the demo, spaced out to ten tiles like the arcade's.
+const GAME_OVER_TEXT: &str = "GAME  OVER";
+
+impl World {
+    // Loads every asset and sets up a game that hasn't started yet.
+    fn new(ctx: &mut Context) -> GameResult<World> {
+        // Load the texture atlas; its manifest names the rect of every sprite.
+        let atlas = SpriteAtlas::load(ctx, "/atlas.json")?;
+
+        // Load the HUD font; its advance of one tile keeps text on the tile grid.
+        let font = BitmapFont::load(ctx, "/fonts/arcade.json")?;
+
+        // Collect wall sprites (16 or 47 variations based on surrounding walls, depending on the manifest).
+        let wall_tileset = WallTileset::from_atlas(&atlas)?;
+
+        // Load every actor's animation clips; frames refer to atlas sprites by name.
+        let animations = AnimationLibrary::load(ctx, "/animations.json", &atlas)?;
+
+        // Look up UV coordinates for the small and big dot sprites.
+        let small_dot_rect = atlas.sprite("dot_small")?;
+        let big_dot_rect = atlas.sprite("dot_big")?;
 
         // Load level map from file
         let mut file = ctx.fs.open("/levels/level1.txt")?;
@@ -105,182 +169,1094 @@ impl GameState {
         file.read_to_string(&mut content)?;
         let level_map = load_level_from_string(&content);
 
-        // Create `display_map` by calculating wall masks for graphical representation.
-        // A wall mask (0-15) indicates which adjacent tiles (N, S, W, E) are also walls.
-        let mut display_map = level_map.clone();
-        for (y, row) in level_map.iter().enumerate() {
-            for (x, &tile_value) in row.iter().enumerate() {
-                if tile_value == 1 {
-                    let mut wall_mask: u8 = 0;
-                    if is_wall_at(x as isize, y as isize - 1, &level_map) {
-                        wall_mask += 1;
-                    } // North
-                    if is_wall_at(x as isize, y as isize + 1, &level_map) {
-                        wall_mask += 2;
-                    } // South
-                    if is_wall_at(x as isize - 1, y as isize, &level_map) {
-                        wall_mask += 4;
-                    } // West
-                    if is_wall_at(x as isize + 1, y as isize, &level_map) {
-                        wall_mask += 8;
-                    } // East
-                    display_map[y][x] = wall_mask + WALL_CODE_OFFSET; // Store with offset for drawing lookup.
-                }
-            }
-        }
+        // Calculate wall masks for graphical representation.
+        // A wall mask indicates which adjacent tiles are also walls; the tileset decides whether diagonals count.
+        let walls = WallAutotiler::with_scheme(&level_map, wall_tileset.scheme());
 
-        // Initialize player's starting position and state.
-        let start_pos = Vec2::new(13.5 * TILE_SIZE, (23.5 * TILE_SIZE) + MAZE_OFFSET_Y);
-        let player = Player {
-            pos: start_pos,
-            direction: Direction::Stopped,
-            desired_direction: Direction::Stopped,
-            sprite_rects: player_rects,
-        };
+        // Trace the vector wall outlines used by `WallStyle::Vector`.
+        let wall_mesh = build_wall_mesh(ctx, &level_map)?;
 
-        // Return the initialized GameState.
-        Ok(GameState {
-            wall_images,
-            sprite_sheet,
-            display_map,
+        // Walls are rendered once into a cached layer; `draw` keeps its resolution in step with the window.
+        let maze_renderer = MazeRenderer::new(ctx, &atlas, &level_map, 1.0);
+
+        // The attract-mode demo replays a recorded game, which has to be of this level.
+        let demo = Replay::load(ctx, "/demo.json")?;
+        let sim = demo.start(level_map.clone()).map_err(|e| {
+            GameError::ResourceLoadError(format!("Demo doesn't fit the level: {}", e))
+        })?;
+
+        let player_animation = animations.animator("player", Direction::West.name())?;
+        let (bindings, bindings_error) = Bindings::load_user(ctx, GAME_BINDINGS);
+
+        let mut world = World {
+            atlas,
+            font,
+            animations,
+            wall_tileset,
             level_map,
+            walls,
+            wall_style: WallStyle::Tiles,
+            wall_mesh,
+            maze_renderer,
             small_dot_rect,
             big_dot_rect,
-            player,
-        })
+            dots_shown: sim.dots_left(),
+            sim,
+            player_animation,
+            ghost_animations: Vec::new(),
+            bindings,
+            controller: Recorder::new(HumanController::new(), Replay::default()),
+            demo,
+            popups: Vec::new(),
+            animation_events: Vec::new(),
+            audio: AudioPlayer::load(ctx, "/sounds.json"),
+            sound_cues: SoundCues::new(),
+            high_scores: HighScoreTable::load_user(ctx),
+            ticks: 0,
+            notice: None,
+        };
+        world.reset_animations()?;
+        let missing = world.audio.missing().len();
+        if missing > 0 {
+            world.show_notice(format!("{} SOUNDS MISSING", missing));
+        }
+        if bindings_error.is_some() {
+            world.show_notice("KEY BINDINGS IGNORED".to_string());
+        }
+        Ok(world)
     }
-}
 
-// --- ggez EventHandler Implementation ---
+    // Shows `text` in the notice row, replacing any notice still up.
+    fn show_notice(&mut self, text: String) {
+        self.notice = Some(Notice {
+            text,
+            time_left: NOTICE_SECONDS,
+        });
+    }
 
-impl EventHandler for GameState {
-    // Called once per game frame to update game logic.
-    fn update(&mut self, ctx: &mut Context) -> GameResult {
-        let dt = ctx.time.delta().as_secs_f32(); // Time elapsed since last frame.
-
-        // Check if moving in the desired direction (player input) is possible.
-        let desired_move_possible = {
-            let mut pos_if_desired = self.player.pos;
-            match self.player.desired_direction {
-                Direction::North => pos_if_desired.y -= PLAYER_SPEED * dt,
-                Direction::South => pos_if_desired.y += PLAYER_SPEED * dt,
-                Direction::West => pos_if_desired.x -= PLAYER_SPEED * dt,
-                Direction::East => pos_if_desired.x += PLAYER_SPEED * dt,
-                Direction::Stopped => {}
+    // Starts a fresh game on the loaded level.
+    fn new_game(&mut self, seed: u32) -> GameResult {
+        self.sim = Simulation::new(self.level_map.clone(), seed);
+        self.sim.scoreboard.high_score = self.high_scores.top_score();
+        let replay = Replay::new(seed, &self.level_map);
+        self.controller = Recorder::new(HumanController::new(), replay);
+        self.popups.clear();
+        self.reset_animations()
+    }
+
+    // Puts every animator back to the start of a life: the player facing west with the
+    // mouth closed, like the arcade, and each ghost looking where it is heading.
+    fn reset_animations(&mut self) -> GameResult {
+        self.player_animation.play(Direction::West.name());
+        self.player_animation.restart();
+        self.player_animation.pause();
+        self.ghost_animations = self
+            .sim
+            .ghosts
+            .iter()
+            .map(|ghost| {
+                let state = ghost.mode.animation_state(ghost.direction);
+                self.animations.animator(ghost.name, &state)
+            })
+            .collect::<GameResult<Vec<_>>>()?;
+        Ok(())
+    }
+
+    // Keeps the animators in line with the simulation after a tick.
+    fn update_animations(&mut self) {
+        // Chomp while moving; when blocked, freeze on the current frame and keep facing the same way.
+        let player = &self.sim.player;
+        if player.direction != Direction::Stopped {
+            self.player_animation.set_state(player.facing.name());
+            self.player_animation.resume();
+        } else {
+            self.player_animation.pause();
+        }
+        let events = self.player_animation.update(TICK_SECONDS);
+        self.animation_events.extend(events);
+
+        // Frightened clips start from their first frame; turning only swaps the eyes and
+        // keeps the body wiggle in phase.
+        for (ghost, animation) in self.sim.ghosts.iter().zip(&mut self.ghost_animations) {
+            let state = ghost.mode.animation_state(ghost.direction);
+            match ghost.mode {
+                GhostMode::Frightened { .. } => animation.play(&state),
+                _ => animation.set_state(&state),
+            }
+            self.animation_events.extend(animation.update(TICK_SECONDS));
+        }
+    }
+
+    // Reacts to what happened on the last tick, in the simulation and in the animations,
+    // with sounds and score popups, and ends the
+    // tick's background loop unless the scene kept it going.
+    fn handle_events(&mut self, ctx: &Context) {
+        for popup in &mut self.popups {
+            popup.time_left -= TICK_SECONDS;
+        }
+        self.popups.retain(|popup| popup.time_left > 0.0);
+        for event in self.sim.drain_events() {
+            if let Some(sound) = self.sound_cues.event_sound(&event) {
+                self.audio.play(ctx, sound);
+            }
+            let (points, pos, color) = match event {
+                GameEvent::GhostEaten { points, pos, .. } => (points, pos, GHOST_POINTS_COLOR),
+                GameEvent::FruitEaten { points, pos, .. } => (points, pos, FRUIT_POINTS_COLOR),
+                _ => continue,
+            };
+            self.popups.push(ScorePopup {
+                points,
+                pos,
+                color,
+                time_left: POPUP_SECONDS,
+            });
+        }
+        for event in self.animation_events.drain(..) {
+            if let Some(sound) = Sound::from_name(&event) {
+                self.audio.play(ctx, sound);
+            }
+        }
+        self.audio.end_tick(ctx);
+    }
+
+    // Re-renders the cached wall layer and dot instances if the maze changed since the last frame.
+    fn prepare_maze(&mut self, ctx: &mut Context) -> GameResult {
+        if self.sim.dots_left() != self.dots_shown {
+            self.dots_shown = self.sim.dots_left();
+            self.maze_renderer.invalidate_dots();
+        }
+        self.maze_renderer
+            .update_walls(ctx, &self.level_map, |canvas| {
+                match self.wall_style {
+                    WallStyle::Tiles => render::draw_wall_tiles(
+                        canvas,
+                        &self.atlas,
+                        &self.wall_tileset,
+                        &self.walls,
+                        &self.level_map,
+                    ),
+                    WallStyle::Vector => canvas.draw(&self.wall_mesh, DrawParam::new()),
+                }
+                Ok(())
+            })?;
+        self.maze_renderer
+            .update_dots(&self.sim.map, self.small_dot_rect, self.big_dot_rect);
+        Ok(())
+    }
+
+    // Switches between bitmap and vector walls.
+    fn toggle_wall_style(&mut self) {
+        self.wall_style = match self.wall_style {
+            WallStyle::Tiles => WallStyle::Vector,
+            WallStyle::Vector => WallStyle::Tiles,
+        };
+        self.maze_renderer.invalidate_walls();
+    }
+
+    // Writes the replay of the game just played to the user data directory, for
+    // reproducing whatever happened in it.
+    fn save_replay(&mut self, ctx: &Context) {
+        self.controller.finish(&self.sim);
+        let path = ctx.fs.user_data_dir().join(LAST_REPLAY_FILE);
+        let text = match self.controller.replay().write(&path) {
+            Ok(()) => "REPLAY SAVED",
+            Err(_) => "REPLAY NOT SAVED",
+        };
+        self.show_notice(text.to_string());
+    }
+
+    fn blink_on(&self) -> bool {
+        let blink_phase = (self.ticks as f32 * TICK_SECONDS / HUD_BLINK_INTERVAL) as u64;
+        blink_phase.is_multiple_of(2)
+    }
+
+    // Draws the score rows above the maze and lives and fruits below it.
+    fn draw_hud(&self, canvas: &mut graphics::Canvas, blink_on: bool) {
+        hud::draw_hud(
+            canvas,
+            &self.atlas,
+            &self.font,
+            &self.sim.scoreboard,
+            blink_on,
+        );
+    }
+
+    // Draws the maze (walls and dots), applying the vertical offset for the UI area.
+    fn draw_maze(&self, canvas: &mut graphics::Canvas, show_walls: bool) {
+        let origin = Vec2::new(0.0, MAZE_OFFSET_Y);
+        if show_walls {
+            self.maze_renderer.draw_walls(canvas, origin);
+        }
+        self.maze_renderer.draw_dots(canvas, origin);
+    }
+
+    fn draw_player(&self, canvas: &mut graphics::Canvas) {
+        // Calculate player drawing position (adjust from center to top-left for sprite).
+        let player_dest = self.sim.player.pos - Vec2::new(TILE_SIZE / 2.0, TILE_SIZE / 2.0);
+        self.player_animation
+            .draw(canvas, self.atlas.image(), player_dest);
+    }
+
+    fn draw_ghosts(&self, canvas: &mut graphics::Canvas) {
+        for (ghost, animation) in self.sim.ghosts.iter().zip(&self.ghost_animations) {
+            let ghost_dest = ghost.pos - Vec2::new(TILE_SIZE / 2.0, TILE_SIZE / 2.0);
+            animation.draw(canvas, self.atlas.image(), ghost_dest);
+        }
+    }
+
+    // Draws the bonus fruit while it waits below the ghost house.
+    fn draw_fruit(&self, canvas: &mut graphics::Canvas) {
+        let Some(state) = &self.sim.fruit else {
+            return;
+        };
+        if let Some(rect) = self.atlas.get(state.fruit.sprite_name()) {
+            let dest = tile_center(FRUIT_TILE) - Vec2::new(TILE_SIZE / 2.0, TILE_SIZE / 2.0);
+            canvas.draw(self.atlas.image(), DrawParam::new().dest(dest).src(rect));
+        }
+    }
+
+    fn draw_popups(&self, canvas: &mut graphics::Canvas) {
+        for popup in &self.popups {
+            let text = popup.points.to_string();
+            let column = popup.pos.x / TILE_SIZE - text.len() as f32 / 2.0;
+            let row = popup.pos.y / TILE_SIZE - 0.5;
+            hud::draw_text(canvas, &self.font, &text, column, row, popup.color);
+        }
+    }
+
+    // Draws the whole playfield as seen during play, ghosts on top of the player like the arcade.
+    fn draw_playfield(&self, canvas: &mut graphics::Canvas) {
+        self.draw_maze(canvas, true);
+        self.draw_hud(canvas, self.blink_on());
+        self.draw_fruit(canvas);
+        self.draw_player(canvas);
+        self.draw_ghosts(canvas);
+        self.draw_popups(canvas);
+    }
+
+    // Draws the high score table with its heading at `row`, `highlight` picking out a place
+    // just entered.
+    fn draw_high_scores(&self, canvas: &mut graphics::Canvas, row: f32, highlight: Option<usize>) {
+        self.draw_message(canvas, "HIGH  SCORES", row, READY_COLOR);
+        for (i, entry) in self.high_scores.entries().iter().enumerate() {
+            let highlighted = Some(i) == highlight;
+            if highlighted && !self.blink_on() {
+                continue;
             }
-            // Create a rectangle representing the player's potential next position.
-            let rect_if_desired = Rect::new(
-                pos_if_desired.x - TILE_SIZE / 2.0,
-                pos_if_desired.y - TILE_SIZE / 2.0,
-                TILE_SIZE,
-                TILE_SIZE,
+            let color = if highlighted {
+                PLAYER_ONE_COLOR
+            } else {
+                Color::WHITE
+            };
+            let entry_row = row + 3.0 + 2.0 * i as f32;
+            hud::draw_text_right(canvas, &self.font, &place_name(i), 7.0, entry_row, color);
+            hud::draw_text(canvas, &self.font, &entry.initials, 10.0, entry_row, color);
+            hud::draw_text_right(
+                canvas,
+                &self.font,
+                &entry.score.to_string(),
+                21.0,
+                entry_row,
+                color,
             );
-            is_rect_walkable(rect_if_desired, &self.level_map)
+        }
+    }
+
+    fn draw_message(&self, canvas: &mut graphics::Canvas, text: &str, row: f32, color: Color) {
+        // Centered on the 28 tile wide screen, leaning left for odd gaps like the arcade.
+        let column = (28usize.saturating_sub(text.chars().count()) / 2) as f32;
+        hud::draw_text(canvas, &self.font, text, column, row, color);
+    }
+}
+
+// Builds a mesh of rounded wall outlines in maze-local pixel coordinates.
+fn build_wall_mesh(ctx: &mut Context, level_map: &Grid<u8>) -> GameResult<graphics::Mesh> {
+    let outlines = outline::maze_outlines(
+        level_map,
+        outline::WALL_INSET,
+        outline::WALL_CORNER_RADIUS,
+        outline::WALL_CORNER_SEGMENTS,
+    );
+    let color = Color::from_rgb(252, 72, 72); // Same red as the bitmap walls.
+
+    let mut builder = graphics::MeshBuilder::new();
+    for outline in &outlines {
+        let mut points: Vec<Vec2> = outline.points.iter().map(|p| *p * TILE_SIZE).collect();
+        if outline.closed
+            && let Some(&first) = points.first()
+        {
+            points.push(first);
+        }
+        if points.len() >= 2 {
+            builder.polyline(graphics::DrawMode::stroke(1.0), &points, color)?;
+        }
+    }
+    Ok(graphics::Mesh::from_data(ctx, builder.build()))
+}
+
+// --- Scenes ---
+//
+// Title (with Demo on top while idle) -> Gameplay (with Ready on top) -> Death / LevelClear -> Ready ... -> GameOver
+// -> HighScoreEntry (for a score that makes the table) -> HighScores -> Title. Ready, Death and LevelClear sit on top of
+// Gameplay but draw the whole playfield themselves; only Pause is an overlay drawn over the gameplay.
+
+// The ghosts as introduced on the title screen: actor, character and nickname.
+const GHOST_ROLL: [(&str, &str, &str); 4] = [
+    ("blinky", "SHADOW", "BLINKY"),
+    ("pinky", "SPEEDY", "PINKY"),
+    ("inky", "BASHFUL", "INKY"),
+    ("clyde", "POKEY", "CLYDE"),
+];
+
+// Title screen timing: each ghost takes one step, revealing its sprite, then its
+// character, then its nickname a third of a step apart. The points table stays up for a
+// while, then the high score table if there is one, then the demo starts.
+const ROLL_STEP_SECONDS: f32 = 1.5;
+const DEMO_DELAY_SECONDS: f32 = 4.0;
+const HIGH_SCORES_SECONDS: f32 = 5.0;
+
+// Attract screen waiting for Enter: the "CHARACTER / NICKNAME" roll, the high scores,
+// then the demo.
+struct TitleScene {
+    time: f32,
+}
+
+impl TitleScene {
+    fn new() -> TitleScene {
+        TitleScene { time: 0.0 }
+    }
+
+    // When the roll gives way to the high score table.
+    fn roll_seconds() -> f32 {
+        ROLL_STEP_SECONDS * GHOST_ROLL.len() as f32 + DEMO_DELAY_SECONDS
+    }
+}
+
+impl Scene<World> for TitleScene {
+    fn update(&mut self, world: &mut World, _ctx: &mut Context) -> Transition<World> {
+        self.time += TICK_SECONDS;
+        let mut demo_time = TitleScene::roll_seconds();
+        if !world.high_scores.is_empty() {
+            demo_time += HIGH_SCORES_SECONDS;
+        }
+        if self.time < demo_time {
+            return Transition::None;
+        }
+        // The roll starts over once the demo pops back to the title.
+        self.time = 0.0;
+        let seed = world.demo.seed;
+        match world.new_game(seed) {
+            Ok(()) => Transition::Push(Box::new(DemoScene { caught: false })),
+            Err(_) => {
+                world.show_notice("CANNOT START DEMO".to_string());
+                Transition::None
+            }
+        }
+    }
+
+    fn action(
+        &mut self,
+        world: &mut World,
+        ctx: &mut Context,
+        action: Action,
+    ) -> Transition<World> {
+        match action {
+            Action::Confirm => {
+                // Seed the ghosts' random turns from the moment the game was started.
+                let seed = ctx.time.time_since_start().as_nanos() as u32;
+                match world.new_game(seed) {
+                    Ok(()) => Transition::Reset(Box::new(GameplayScene {
+                        intro_pending: true,
+                    })),
+                    Err(_) => {
+                        world.show_notice("CANNOT START GAME".to_string());
+                        Transition::None
+                    }
+                }
+            }
+            Action::Back => Transition::Pop,
+            _ => Transition::None,
+        }
+    }
+
+    fn draw(&self, world: &World, canvas: &mut graphics::Canvas) {
+        world.draw_hud(canvas, true);
+        if world.blink_on() {
+            world.draw_message(canvas, "PUSH ENTER", 30.0, PLAYER_ONE_COLOR);
+        }
+        if self.time >= TitleScene::roll_seconds() && !world.high_scores.is_empty() {
+            world.draw_high_scores(canvas, 5.0, None);
+            return;
+        }
+        hud::draw_text(
+            canvas,
+            &world.font,
+            "CHARACTER / NICKNAME",
+            7.0,
+            5.0,
+            Color::WHITE,
+        );
+
+        for (i, &(actor, character, nickname)) in GHOST_ROLL.iter().enumerate() {
+            let color = ghost::ghost_color(actor);
+            let shown = (self.time / ROLL_STEP_SECONDS - i as f32) * 3.0;
+            let row = 7.0 + 3.0 * i as f32;
+            if shown >= 0.0 {
+                let dest = Vec2::new(4.0 * TILE_SIZE, row * TILE_SIZE);
+                for sprite in [format!("{}_0", actor), "ghost_eyes_east".to_string()] {
+                    if let Some(rect) = world.atlas.get(&sprite) {
+                        canvas.draw(world.atlas.image(), DrawParam::new().dest(dest).src(rect));
+                    }
+                }
+            }
+            if shown >= 1.0 {
+                let text = format!("-{}", character);
+                hud::draw_text(canvas, &world.font, &text, 7.0, row, color);
+            }
+            if shown >= 2.0 {
+                let text = format!("\"{}\"", nickname);
+                hud::draw_text(canvas, &world.font, &text, 18.0, row, color);
+            }
+        }
+
+        // The points table appears once every ghost is introduced.
+        if self.time >= ROLL_STEP_SECONDS * GHOST_ROLL.len() as f32 {
+            for (row, sprite, points) in [(20.0, "dot_small", "10"), (22.0, "dot_big", "50")] {
+                if let Some(rect) = world.atlas.get(sprite) {
+                    let dest = Vec2::new(10.0 * TILE_SIZE, row * TILE_SIZE);
+                    canvas.draw(world.atlas.image(), DrawParam::new().dest(dest).src(rect));
+                }
+                hud::draw_text(canvas, &world.font, points, 12.0, row, Color::WHITE);
+                hud::draw_text(canvas, &world.font, "PTS", 15.0, row, Color::WHITE);
+            }
+        }
+
+        if let Some(best) = world.high_scores.entries().first() {
+            let best = format!("BEST  {}  {}", best.initials, best.score);
+            world.draw_message(canvas, &best, 26.0, Color::WHITE);
+        }
+    }
+}
+
+// The attract-mode demo: the recorded game in `resources/demo.json` played through the
+// normal simulation. Ends when the player dies or the replay runs out; any key ends it early.
+struct DemoScene {
+    caught: bool, // The death animation is playing.
+}
+
+impl Scene<World> for DemoScene {
+    fn update(&mut self, world: &mut World, _ctx: &mut Context) -> Transition<World> {
+        if self.caught {
+            // Its death sound is dropped too.
+            world.player_animation.update(TICK_SECONDS);
+            return if world.player_animation.is_finished() {
+                Transition::Pop
+            } else {
+                Transition::None
+            };
+        }
+
+        let tick = world.sim.tick_count();
+        if world.demo.is_finished(tick) || world.sim.is_level_cleared() {
+            return Transition::Pop;
+        }
+        world.sim.step(&mut world.demo);
+        // The attract mode is silent, like the arcade's.
+        world.sim.drain_events().for_each(drop);
+        world.update_animations();
+        world.animation_events.clear();
+        if world.sim.is_player_caught() {
+            self.caught = true;
+            world.player_animation.play("death");
+            world.player_animation.resume();
+        }
+        Transition::None
+    }
+
+    fn action(
+        &mut self,
+        _world: &mut World,
+        _ctx: &mut Context,
+        _action: Action,
+    ) -> Transition<World> {
+        Transition::Pop
+    }
+
+    fn draw(&self, world: &World, canvas: &mut graphics::Canvas) {
+        world.draw_maze(canvas, true);
+        world.draw_hud(canvas, true);
+        world.draw_player(canvas);
+        if !self.caught {
+            world.draw_ghosts(canvas);
+        }
+        world.draw_message(canvas, GAME_OVER_TEXT, MESSAGE_ROW, GAME_OVER_COLOR);
+    }
+}
+
+// The running game. The simulation only advances while this scene is on top.
+struct GameplayScene {
+    intro_pending: bool, // A fresh game opens with the long "PLAYER ONE" intro.
+}
+
+impl Scene<World> for GameplayScene {
+    fn update(&mut self, world: &mut World, ctx: &mut Context) -> Transition<World> {
+        if self.intro_pending {
+            self.intro_pending = false;
+            world.audio.play(ctx, Sound::Intro);
+            return Transition::Push(Box::new(ReadyScene::new(true)));
+        }
+
+        world.sim.step(&mut world.controller);
+        world.update_animations();
+        let (sound, pitch) = SoundCues::background_loop(&world.sim);
+        world.audio.keep_loop(ctx, sound, pitch);
+
+        if world.sim.is_player_caught() {
+            Transition::Push(Box::new(DeathScene { time: 0.0 }))
+        } else if world.sim.is_level_cleared() {
+            Transition::Push(Box::new(LevelClearScene { time: 0.0 }))
+        } else {
+            Transition::None
+        }
+    }
+
+    fn action(
+        &mut self,
+        world: &mut World,
+        _ctx: &mut Context,
+        action: Action,
+    ) -> Transition<World> {
+        // Set the player's desired direction based on the movement actions.
+        match action {
+            Action::MoveUp => world.controller.inner.request(Direction::North),
+            Action::MoveDown => world.controller.inner.request(Direction::South),
+            Action::MoveLeft => world.controller.inner.request(Direction::West),
+            Action::MoveRight => world.controller.inner.request(Direction::East),
+            Action::ToggleWalls => world.toggle_wall_style(),
+            Action::Pause | Action::Back => return Transition::Push(Box::new(PauseScene)),
+            _ => {}
+        }
+        Transition::None
+    }
+
+    fn draw(&self, world: &World, canvas: &mut graphics::Canvas) {
+        world.draw_playfield(canvas);
+    }
+}
+
+// Seconds the "READY!" message stays up, longer before the first life of a game.
+const READY_SECONDS: f32 = 2.0;
+const FIRST_READY_SECONDS: f32 = 4.0;
+
+// "READY!" over the frozen playfield before each life.
+struct ReadyScene {
+    time: f32,
+    first: bool, // Start of a game: "PLAYER ONE" is shown too and the actors are hidden at first.
+}
+
+impl ReadyScene {
+    fn new(first: bool) -> ReadyScene {
+        ReadyScene { time: 0.0, first }
+    }
+}
+
+impl Scene<World> for ReadyScene {
+    fn update(&mut self, _world: &mut World, _ctx: &mut Context) -> Transition<World> {
+        self.time += TICK_SECONDS;
+        let length = if self.first {
+            FIRST_READY_SECONDS
+        } else {
+            READY_SECONDS
         };
+        if self.time >= length {
+            Transition::Pop
+        } else {
+            Transition::None
+        }
+    }
 
-        // If a desired direction is set and it's walkable, change the player's actual movement direction.
-        if self.player.desired_direction != Direction::Stopped && desired_move_possible {
-            self.player.direction = self.player.desired_direction;
+    fn action(
+        &mut self,
+        world: &mut World,
+        _ctx: &mut Context,
+        action: Action,
+    ) -> Transition<World> {
+        if action == Action::ToggleWalls {
+            world.toggle_wall_style();
         }
+        Transition::None
+    }
+
+    fn draw(&self, world: &World, canvas: &mut graphics::Canvas) {
+        // The first half of a game's intro shows "PLAYER ONE" over an empty maze.
+        let announcing = self.first && self.time < FIRST_READY_SECONDS / 2.0;
+        world.draw_maze(canvas, true);
+        world.draw_hud(canvas, world.blink_on());
+        if announcing {
+            world.draw_message(canvas, "PLAYER ONE", PLAYER_ONE_ROW, PLAYER_ONE_COLOR);
+        } else {
+            world.draw_player(canvas);
+            world.draw_ghosts(canvas);
+        }
+        world.draw_message(canvas, "READY!", MESSAGE_ROW, READY_COLOR);
+    }
+}
 
-        // Calculate the player's next position based on the current movement direction.
-        let mut next_pos = self.player.pos;
-        match self.player.direction {
-            Direction::North => next_pos.y -= PLAYER_SPEED * dt,
-            Direction::South => next_pos.y += PLAYER_SPEED * dt,
-            Direction::West => next_pos.x -= PLAYER_SPEED * dt,
-            Direction::East => next_pos.x += PLAYER_SPEED * dt,
-            Direction::Stopped => {}
+// Frozen game with "PAUSED" on top until pause or back is pressed again.
+struct PauseScene;
+
+impl Scene<World> for PauseScene {
+    fn update(&mut self, _world: &mut World, _ctx: &mut Context) -> Transition<World> {
+        Transition::None
+    }
+
+    fn action(
+        &mut self,
+        _world: &mut World,
+        _ctx: &mut Context,
+        action: Action,
+    ) -> Transition<World> {
+        match action {
+            Action::Pause | Action::Back => Transition::Pop,
+            _ => Transition::None,
         }
+    }
+
+    fn draw(&self, world: &World, canvas: &mut graphics::Canvas) {
+        world.draw_message(canvas, "PAUSED", MESSAGE_ROW, READY_COLOR);
+    }
+
+    fn is_overlay(&self) -> bool {
+        true
+    }
+}
+
+// Seconds everything freezes after the player is caught, before the death animation starts,
+// and after it ends.
+const DEATH_FREEZE_SECONDS: f32 = 1.0;
+const DEATH_END_SECONDS: f32 = 0.5;
+
+// The player caught by a ghost: a short freeze, then the death animation without ghosts.
+struct DeathScene {
+    time: f32,
+}
+
+impl Scene<World> for DeathScene {
+    fn update(&mut self, world: &mut World, ctx: &mut Context) -> Transition<World> {
+        let was_frozen = self.time < DEATH_FREEZE_SECONDS;
+        self.time += TICK_SECONDS;
+        if self.time < DEATH_FREEZE_SECONDS {
+            return Transition::None;
+        }
+        if was_frozen {
+            world.player_animation.play("death");
+            world.player_animation.resume();
+        }
+        let events = world.player_animation.update(TICK_SECONDS);
+        world.animation_events.extend(events);
+        if !world.player_animation.is_finished() {
+            // Measure the closing pause from the end of the animation.
+            self.time = DEATH_FREEZE_SECONDS;
+            return Transition::None;
+        }
+        if self.time < DEATH_FREEZE_SECONDS + DEATH_END_SECONDS {
+            return Transition::None;
+        }
+
+        if !world.sim.lose_life() {
+            world.save_replay(ctx);
+            return Transition::Reset(Box::new(GameOverScene { time: 0.0 }));
+        }
+        if world.reset_animations().is_err() {
+            world.show_notice("CANNOT RESET ANIMATIONS".to_string());
+        }
+        // Back to the gameplay below, which resumes after the next "READY!".
+        Transition::Replace(Box::new(ReadyScene::new(false)))
+    }
+
+    fn draw(&self, world: &World, canvas: &mut graphics::Canvas) {
+        world.draw_maze(canvas, true);
+        world.draw_hud(canvas, world.blink_on());
+        world.draw_player(canvas);
+        if self.time < DEATH_FREEZE_SECONDS {
+            world.draw_ghosts(canvas);
+        }
+    }
+}
+
+// Level clear: a pause, then the walls flash before the maze is refilled.
+const LEVEL_CLEAR_PAUSE_SECONDS: f32 = 1.0;
+const LEVEL_CLEAR_FLASH_SECONDS: f32 = 0.2; // Per on or off phase.
+const LEVEL_CLEAR_FLASHES: u32 = 4;
+
+struct LevelClearScene {
+    time: f32,
+}
+
+impl Scene<World> for LevelClearScene {
+    fn update(&mut self, world: &mut World, _ctx: &mut Context) -> Transition<World> {
+        self.time += TICK_SECONDS;
+        let length = LEVEL_CLEAR_PAUSE_SECONDS
+            + LEVEL_CLEAR_FLASH_SECONDS * 2.0 * LEVEL_CLEAR_FLASHES as f32;
+        if self.time < length {
+            return Transition::None;
+        }
+        world.sim.next_level();
+        if world.reset_animations().is_err() {
+            world.show_notice("CANNOT RESET ANIMATIONS".to_string());
+        }
+        Transition::Replace(Box::new(ReadyScene::new(false)))
+    }
+
+    fn draw(&self, world: &World, canvas: &mut graphics::Canvas) {
+        // Walls blink off and on; the ghosts are gone while they do.
+        let flashing = self.time >= LEVEL_CLEAR_PAUSE_SECONDS;
+        let phase = ((self.time - LEVEL_CLEAR_PAUSE_SECONDS) / LEVEL_CLEAR_FLASH_SECONDS) as u32;
+        world.draw_maze(canvas, !flashing || !phase.is_multiple_of(2));
+        world.draw_hud(canvas, world.blink_on());
+        world.draw_player(canvas);
+        if !flashing {
+            world.draw_ghosts(canvas);
+        }
+    }
+}
+
+// Seconds `GAME_OVER_TEXT` stays up.
+const GAME_OVER_SECONDS: f32 = 3.0;
+
+struct GameOverScene {
+    time: f32,
+}
+
+impl Scene<World> for GameOverScene {
+    fn update(&mut self, world: &mut World, _ctx: &mut Context) -> Transition<World> {
+        self.time += TICK_SECONDS;
+        if self.time < GAME_OVER_SECONDS {
+            return Transition::None;
+        }
+        if world.high_scores.qualifies(world.sim.scoreboard.score()) {
+            Transition::Replace(Box::new(HighScoreEntryScene::new()))
+        } else if world.high_scores.is_empty() {
+            Transition::Replace(Box::new(TitleScene::new()))
+        } else {
+            Transition::Replace(Box::new(HighScoresScene::new(None)))
+        }
+    }
+
+    fn draw(&self, world: &World, canvas: &mut graphics::Canvas) {
+        world.draw_maze(canvas, true);
+        world.draw_hud(canvas, true);
+        world.draw_message(canvas, GAME_OVER_TEXT, MESSAGE_ROW, GAME_OVER_COLOR);
+    }
+}
+
+// Three-letter initials for a score that made the table: up/down pick a letter,
+// left/right or Enter move between them, Enter on the last one confirms.
+struct HighScoreEntryScene {
+    letters: [usize; INITIALS_LEN], // Indices into `INITIAL_CHARS`, cycled with up and down.
+    cursor: usize,
+}
+
+impl HighScoreEntryScene {
+    fn new() -> HighScoreEntryScene {
+        HighScoreEntryScene {
+            letters: [0; INITIALS_LEN],
+            cursor: 0,
+        }
+    }
+
+    fn initials(&self) -> String {
+        self.letters
+            .iter()
+            .map(|&i| INITIAL_CHARS[i] as char)
+            .collect()
+    }
+}
 
-        // Create a rectangle representing the player's potential next position for collision check.
-        let next_player_rect = Rect::new(
-            next_pos.x - TILE_SIZE / 2.0,
-            next_pos.y - TILE_SIZE / 2.0,
-            TILE_SIZE,
-            TILE_SIZE,
+impl Scene<World> for HighScoreEntryScene {
+    fn update(&mut self, _world: &mut World, _ctx: &mut Context) -> Transition<World> {
+        Transition::None
+    }
+
+    fn action(
+        &mut self,
+        world: &mut World,
+        ctx: &mut Context,
+        action: Action,
+    ) -> Transition<World> {
+        let count = INITIAL_CHARS.len();
+        let letter = &mut self.letters[self.cursor];
+        match action {
+            Action::MoveUp => *letter = (*letter + 1) % count,
+            Action::MoveDown => *letter = (*letter + count - 1) % count,
+            Action::MoveLeft => self.cursor = self.cursor.saturating_sub(1),
+            Action::MoveRight => self.cursor = (self.cursor + 1).min(self.letters.len() - 1),
+            Action::Confirm if self.cursor + 1 < self.letters.len() => self.cursor += 1,
+            Action::Confirm => {
+                let score = world.sim.scoreboard.score();
+                let place = world.high_scores.insert(&self.initials(), score);
+                if world.high_scores.save_user(ctx).is_err() {
+                    world.show_notice("HIGH SCORES NOT SAVED".to_string());
+                }
+                return Transition::Replace(Box::new(HighScoresScene::new(place)));
+            }
+            _ => {}
+        }
+        Transition::None
+    }
+
+    fn draw(&self, world: &World, canvas: &mut graphics::Canvas) {
+        world.draw_hud(canvas, true);
+        world.draw_message(canvas, "NEW HIGH SCORE", 10.0, READY_COLOR);
+        world.draw_message(
+            canvas,
+            &world.sim.scoreboard.score().to_string(),
+            13.0,
+            Color::WHITE,
         );
+        world.draw_message(canvas, "ENTER YOUR INITIALS", 17.0, Color::WHITE);
 
-        // If the player's next position is walkable, update their position; otherwise, stop.
-        if is_rect_walkable(next_player_rect, &self.level_map) {
-            self.player.pos = next_pos;
+        let column = 12.0;
+        hud::draw_text(
+            canvas,
+            &world.font,
+            &self.initials(),
+            column,
+            20.0,
+            PLAYER_ONE_COLOR,
+        );
+        if world.blink_on() {
+            hud::draw_text(
+                canvas,
+                &world.font,
+                "-",
+                column + self.cursor as f32,
+                21.0,
+                PLAYER_ONE_COLOR,
+            );
+        }
+    }
+}
+
+// The high score table after a game, with the place just entered blinking. Ends after a
+// while or on Enter.
+struct HighScoresScene {
+    time: f32,
+    highlight: Option<usize>,
+}
+
+impl HighScoresScene {
+    fn new(highlight: Option<usize>) -> HighScoresScene {
+        HighScoresScene {
+            time: 0.0,
+            highlight,
+        }
+    }
+}
+
+impl Scene<World> for HighScoresScene {
+    fn update(&mut self, _world: &mut World, _ctx: &mut Context) -> Transition<World> {
+        self.time += TICK_SECONDS;
+        if self.time < HIGH_SCORES_SECONDS {
+            Transition::None
         } else {
-            self.player.direction = Direction::Stopped;
+            Transition::Replace(Box::new(TitleScene::new()))
+        }
+    }
+
+    fn action(
+        &mut self,
+        _world: &mut World,
+        _ctx: &mut Context,
+        action: Action,
+    ) -> Transition<World> {
+        match action {
+            Action::Confirm | Action::Back => Transition::Replace(Box::new(TitleScene::new())),
+            _ => Transition::None,
+        }
+    }
+
+    fn draw(&self, world: &World, canvas: &mut graphics::Canvas) {
+        world.draw_hud(canvas, true);
+        world.draw_high_scores(canvas, 5.0, self.highlight);
+    }
+}
+
+// --- ggez EventHandler Implementation ---
+
+// The shared world, the stack of screens running on it and how they fit the window.
+struct GameState {
+    world: World,
+    scenes: SceneStack<World>,
+    viewport: Viewport, // Integer scaling by default; F2 switches to fit scaling.
+    debug_overlay: DebugOverlay, // Tiles, hitboxes and ghost targets, toggled with F3.
+    show_debug: bool,
+    gamepads: GamepadInput, // Controllers and the players they are assigned to.
+}
+
+impl GameState {
+    // Creates a new GameState, loading assets and opening on the title screen.
+    fn new(ctx: &mut Context) -> GameResult<GameState> {
+        let world = World::new(ctx)?;
+        let debug_overlay = DebugOverlay::new(ctx, &world.level_map)?;
+        Ok(GameState {
+            world,
+            scenes: SceneStack::new(Box::new(TitleScene::new())),
+            viewport: Viewport::new(SCREEN_WIDTH, SCREEN_HEIGHT, ScaleMode::Integer),
+            debug_overlay,
+            show_debug: false,
+            gamepads: GamepadInput::new(),
+        })
+    }
+
+    // Passes on an action from `player`'s controller. Only the player in play steers;
+    // anyone can use the menus.
+    fn gamepad_action(&mut self, ctx: &mut Context, input: Option<(usize, Action)>) {
+        let Some((player, action)) = input else {
+            return;
+        };
+        let is_move = matches!(
+            action,
+            Action::MoveUp | Action::MoveDown | Action::MoveLeft | Action::MoveRight
+        );
+        if !is_move || player == self.world.sim.scoreboard.current_player {
+            self.scenes.action(&mut self.world, ctx, action);
         }
+    }
+}
 
+impl EventHandler for GameState {
+    // Advances the scenes in fixed ticks, as many as fit in the time since the last frame.
+    fn update(&mut self, ctx: &mut Context) -> GameResult {
+        // ggez has no connect events, so controllers are matched up with players here.
+        let connected = ctx.gamepad.gamepads().map(|(id, _)| id);
+        for (player, _, connected) in self.gamepads.sync(connected) {
+            let state = if connected {
+                "CONNECTED"
+            } else {
+                "DISCONNECTED"
+            };
+            self.world
+                .show_notice(format!("PLAYER {} PAD {}", player + 1, state));
+        }
+
+        while ctx.time.check_update_time(TICK_RATE) {
+            self.world.ticks += 1;
+            if let Some(notice) = &mut self.world.notice {
+                notice.time_left -= TICK_SECONDS;
+                if notice.time_left <= 0.0 {
+                    self.world.notice = None;
+                }
+            }
+            self.scenes.update(&mut self.world, ctx);
+            self.world.handle_events(ctx);
+        }
+        if self.scenes.is_empty() {
+            ctx.request_quit();
+        }
         Ok(())
     }
 
-    // Handles keyboard key press events.
+    // Handles keyboard key press events, turning bound keys into actions.
     fn key_down_event(
         &mut self,
-        _ctx: &mut Context,
+        ctx: &mut Context,
         input: KeyInput,
         _repeated: bool,
     ) -> GameResult {
-        if let Some(keycode) = input.keycode {
-            // Set the player's desired direction based on arrow key input.
-            match keycode {
-                KeyCode::Up => self.player.desired_direction = Direction::North,
-                KeyCode::Down => self.player.desired_direction = Direction::South,
-                KeyCode::Left => self.player.desired_direction = Direction::West,
-                KeyCode::Right => self.player.desired_direction = Direction::East,
-                _ => {} // Ignore other key presses.
+        let Some(action) = input
+            .keycode
+            .and_then(|key| self.world.bindings.action(key))
+        else {
+            return Ok(());
+        };
+        match action {
+            Action::ToggleScaling => self.viewport.mode = self.viewport.mode.toggled(),
+            Action::ToggleDebug => self.show_debug = !self.show_debug,
+            Action::VolumeDown | Action::VolumeUp => {
+                let step = if action == Action::VolumeUp {
+                    VOLUME_STEP
+                } else {
+                    -VOLUME_STEP
+                };
+                let audio = &mut self.world.audio;
+                audio.set_volume(audio.volume() + step);
+                let volume = audio.volume();
+                self.world
+                    .show_notice(format!("VOLUME {:.0}%", volume * 100.0));
             }
+            Action::ToggleMute => {
+                self.world.audio.toggle_mute();
+                let state = if self.world.audio.is_muted() {
+                    "OFF"
+                } else {
+                    "ON"
+                };
+                self.world.show_notice(format!("SOUND {}", state));
+            }
+            _ => self.scenes.action(&mut self.world, ctx, action),
         }
         Ok(())
     }
 
+    // Gamepad events feed the same actions as the keys they stand in for.
+    fn gamepad_button_down_event(
+        &mut self,
+        ctx: &mut Context,
+        button: Button,
+        id: GamepadId,
+    ) -> GameResult {
+        let input = self.gamepads.button_down(id, button);
+        self.gamepad_action(ctx, input);
+        Ok(())
+    }
+
+    fn gamepad_button_up_event(
+        &mut self,
+        ctx: &mut Context,
+        button: Button,
+        id: GamepadId,
+    ) -> GameResult {
+        let input = self.gamepads.button_up(id, button);
+        self.gamepad_action(ctx, input);
+        Ok(())
+    }
+
+    fn gamepad_axis_event(
+        &mut self,
+        ctx: &mut Context,
+        axis: Axis,
+        value: f32,
+        id: GamepadId,
+    ) -> GameResult {
+        let input = self.gamepads.axis(id, axis, value);
+        self.gamepad_action(ctx, input);
+        Ok(())
+    }
+
     // Called once per game frame to draw everything to the screen.
     fn draw(&mut self, ctx: &mut Context) -> GameResult {
-        // Create a new drawing canvas with a black background and set the coordinate system.
+        // Render the wall layer at the window's resolution so vector walls stay smooth.
+        let layer_scale = self.viewport.window_scale(ctx).ceil();
+        self.world.maze_renderer.set_layer_scale(layer_scale);
+        self.world.prepare_maze(ctx)?;
+
+        // Create a new drawing canvas with a black background; the viewport centers the
+        // logical screen in the window and leaves black bars around it.
         let mut canvas = graphics::Canvas::from_frame(ctx, Color::BLACK);
-        canvas.set_screen_coordinates(Rect::new(0.0, 0.0, 224.0, 288.0)); // Original Pac-Man resolution.
+        self.viewport.apply(ctx, &mut canvas)?;
         canvas.set_sampler(graphics::Sampler::nearest_clamp()); // For pixel-perfect rendering.
 
-        // Draw the maze based on the `display_map`.
-        for (y, row) in self.display_map.iter().enumerate() {
-            for (x, &tile_type) in row.iter().enumerate() {
-                let dest_x = x as f32 * TILE_SIZE;
-                let dest_y = (y as f32 * TILE_SIZE) + MAZE_OFFSET_Y; // Apply vertical offset for the maze.
-                let dest_point = Vec2::new(dest_x, dest_y);
-
-                match tile_type {
-                    // Draw walls using the pre-calculated wall images based on the wall mask.
-                    code @ WALL_CODE_OFFSET..=115 => {
-                        let wall_index = (code - WALL_CODE_OFFSET) as usize;
-                        canvas.draw(&self.wall_images[wall_index], dest_point);
-                    }
-                    // Draw small dots using the sprite sheet.
-                    2 => {
-                        let params = DrawParam::new().dest(dest_point).src(self.small_dot_rect);
-                        canvas.draw(&self.sprite_sheet, params);
-                    }
-                    // Draw big dots (power pellets) using the sprite sheet.
-                    3 => {
-                        let params = DrawParam::new().dest(dest_point).src(self.big_dot_rect);
-                        canvas.draw(&self.sprite_sheet, params);
-                    }
-                    _ => {} // Ignore empty spaces (0) for drawing.
-                }
-            }
+        self.scenes.draw(&self.world, &mut canvas);
+        if let Some(notice) = &self.world.notice {
+            self.world
+                .draw_message(&mut canvas, &notice.text, NOTICE_ROW, Color::WHITE);
+        }
+        if self.show_debug {
+            let fps = ctx.time.fps();
+            self.debug_overlay
+                .draw(ctx, &mut canvas, &self.world.sim, fps)?;
         }
-
-        // Determine the correct player sprite frame based on the current movement direction.
-        let player_sprite_rect = match self.player.direction {
-            Direction::North => self.player.sprite_rects[0],
-            Direction::East => self.player.sprite_rects[1],
-            Direction::South => self.player.sprite_rects[2],
-            Direction::West => self.player.sprite_rects[3],
-            Direction::Stopped => self.player.sprite_rects[2], // Default to facing South when stopped.
-        };
-
-        // Calculate player drawing position (adjust from center to top-left for sprite).
-        let player_dest = self.player.pos - Vec2::new(TILE_SIZE / 2.0, TILE_SIZE / 2.0);
-
-        // Draw the player sprite.
-        let params = DrawParam::new().dest(player_dest).src(player_sprite_rect);
-        canvas.draw(&self.sprite_sheet, params);
 
         // Present the drawn frame to the screen.
         canvas.finish(ctx)?;
@@ -0,0 +1,429 @@
+use crate::Grid;
+use ggez::glam::Vec2;
+use std::collections::BTreeMap;
+use std::fmt::Write;
+
+// A wall outline in tile units: (0, 0) is the top-left corner of the map and (1, 0) is
+// one tile to the right. Closed outlines don't repeat their first point at the end.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Polyline {
+    pub points: Vec<Vec2>,
+    pub closed: bool,
+}
+
+// Settings for `maze_outlines` that match the arcade look. The inset turns
+// one-tile-thick walls into a double line, like the outer border.
+pub const WALL_INSET: f32 = 0.375;
+pub const WALL_CORNER_RADIUS: f32 = 0.5;
+pub const WALL_CORNER_SEGMENTS: usize = 4;
+
+// A lattice point on tile corners, and a directed unit edge between two of them.
+type Point = (i32, i32);
+type Edge = (Point, Point);
+
+// Traces the boundary between wall and non-wall tiles as polylines along tile edges.
+// Each outline keeps the wall on its right-hand side (screen coordinates, y down) and
+// only has points where it turns. Outlines around free-standing blocks are closed;
+// outlines that run into the map edge (e.g. at the tunnel) are left open there.
+pub fn trace_wall_outlines(map: &Grid<u8>) -> Vec<Polyline> {
+    // Directed unit edges between a wall cell and an in-bounds non-wall neighbor.
+    let mut edges: Vec<Edge> = Vec::new();
+    for (x, y, &tile) in map.iter() {
+        if tile != 1 {
+            continue;
+        }
+        let (x, y) = (x as i32, y as i32);
+        let open = |dx: i32, dy: i32| {
+            map.get((x + dx) as isize, (y + dy) as isize)
+                .is_some_and(|&t| t != 1)
+        };
+        if open(0, -1) {
+            edges.push(((x, y), (x + 1, y)));
+        }
+        if open(1, 0) {
+            edges.push(((x + 1, y), (x + 1, y + 1)));
+        }
+        if open(0, 1) {
+            edges.push(((x + 1, y + 1), (x, y + 1)));
+        }
+        if open(-1, 0) {
+            edges.push(((x, y + 1), (x, y)));
+        }
+    }
+
+    let mut outgoing: BTreeMap<Point, Vec<usize>> = BTreeMap::new();
+    let mut incoming: BTreeMap<Point, usize> = BTreeMap::new();
+    for (i, &(start, end)) in edges.iter().enumerate() {
+        outgoing.entry(start).or_default().push(i);
+        *incoming.entry(end).or_default() += 1;
+    }
+
+    let mut used = vec![false; edges.len()];
+    let mut outlines = Vec::new();
+
+    // Open outlines first: they start where an edge leaves a point nothing leads into.
+    for i in 0..edges.len() {
+        if !used[i] && !incoming.contains_key(&edges[i].0) {
+            outlines.push(follow(&edges, &outgoing, &mut used, i, false));
+        }
+    }
+    // Everything left forms closed loops.
+    for i in 0..edges.len() {
+        if !used[i] {
+            outlines.push(follow(&edges, &outgoing, &mut used, i, true));
+        }
+    }
+
+    outlines.into_iter().map(simplify).collect()
+}
+
+// Walks connected edges starting at `first`, preferring right turns where several
+// edges meet (two walls touching diagonally) so each outline hugs a single wall.
+fn follow(
+    edges: &[Edge],
+    outgoing: &BTreeMap<Point, Vec<usize>>,
+    used: &mut [bool],
+    first: usize,
+    closed: bool,
+) -> Polyline {
+    let start = edges[first].0;
+    let mut points = vec![to_vec2(start)];
+    let mut current = first;
+    loop {
+        used[current] = true;
+        let (from, to) = edges[current];
+        if closed && to == start {
+            break;
+        }
+        points.push(to_vec2(to));
+
+        let heading = (to.0 - from.0, to.1 - from.1);
+        let right = (-heading.1, heading.0);
+        let left = (heading.1, -heading.0);
+        let next = outgoing.get(&to).and_then(|candidates| {
+            [right, heading, left].into_iter().find_map(|turn| {
+                candidates.iter().copied().find(|&e| {
+                    let (a, b) = edges[e];
+                    !used[e] && (b.0 - a.0, b.1 - a.1) == turn
+                })
+            })
+        });
+        match next {
+            Some(e) => current = e,
+            None => break,
+        }
+    }
+    Polyline { points, closed }
+}
+
+fn to_vec2((x, y): Point) -> Vec2 {
+    Vec2::new(x as f32, y as f32)
+}
+
+// Drops points that lie on a straight line between their neighbors.
+fn simplify(outline: Polyline) -> Polyline {
+    let n = outline.points.len();
+    if n < 3 {
+        return outline;
+    }
+    let points = &outline.points;
+    let keep = |i: usize| {
+        if !outline.closed && (i == 0 || i == n - 1) {
+            return true;
+        }
+        let prev = points[(i + n - 1) % n];
+        let next = points[(i + 1) % n];
+        let a = points[i] - prev;
+        let b = next - points[i];
+        a.perp_dot(b).abs() > f32::EPSILON
+    };
+    Polyline {
+        points: (0..n).filter(|&i| keep(i)).map(|i| points[i]).collect(),
+        closed: outline.closed,
+    }
+}
+
+// Moves an outline traced by `trace_wall_outlines` into the wall by `amount` tiles.
+// Insetting both sides of a one-tile-thick wall gives the arcade's double line.
+pub fn inset_outline(outline: &Polyline, amount: f32) -> Polyline {
+    let n = outline.points.len();
+    let points = &outline.points;
+    // Normal pointing into the wall, i.e. to the right of the edge from `a` to `b`.
+    let normal = |a: Vec2, b: Vec2| {
+        let d = (b - a).normalize_or_zero();
+        Vec2::new(-d.y, d.x)
+    };
+    let moved = (0..n)
+        .map(|i| {
+            let has_prev = outline.closed || i > 0;
+            let has_next = outline.closed || i + 1 < n;
+            let prev = has_prev.then(|| normal(points[(i + n - 1) % n], points[i]));
+            let next = has_next.then(|| normal(points[i], points[(i + 1) % n]));
+            let offset = match (prev, next) {
+                // Corners of axis-aligned outlines move along both edge normals.
+                (Some(a), Some(b)) if a != b => a + b,
+                (Some(a), _) | (_, Some(a)) => a,
+                (None, None) => Vec2::ZERO,
+            };
+            points[i] + offset * amount
+        })
+        .collect();
+    Polyline {
+        points: moved,
+        closed: outline.closed,
+    }
+}
+
+// Replaces every corner with a curve of `segments` steps, cutting at most `radius`
+// tiles (and never more than half of either adjacent edge) off the corner.
+pub fn round_corners(outline: &Polyline, radius: f32, segments: usize) -> Polyline {
+    let n = outline.points.len();
+    if n < 3 || radius <= 0.0 || segments == 0 {
+        return outline.clone();
+    }
+    let points = &outline.points;
+    let mut rounded = Vec::with_capacity(n * (segments + 1));
+    for i in 0..n {
+        let is_end = !outline.closed && (i == 0 || i == n - 1);
+        if is_end {
+            rounded.push(points[i]);
+            continue;
+        }
+        let corner = points[i];
+        let prev = points[(i + n - 1) % n];
+        let next = points[(i + 1) % n];
+        let cut = radius
+            .min(corner.distance(prev) / 2.0)
+            .min(corner.distance(next) / 2.0);
+        let entry = corner + (prev - corner).normalize_or_zero() * cut;
+        let exit = corner + (next - corner).normalize_or_zero() * cut;
+        // Quadratic Bézier from `entry` to `exit` with the corner as its control point.
+        for step in 0..=segments {
+            let t = step as f32 / segments as f32;
+            let u = 1.0 - t;
+            let point = entry * (u * u) + corner * (2.0 * u * t) + exit * (t * t);
+            // Corners half an edge apart share a point; don't emit it twice.
+            if rounded.last() != Some(&point) {
+                rounded.push(point);
+            }
+        }
+    }
+    // The same goes for the last corner of a closed outline and the first.
+    if outline.closed && rounded.len() > 1 && rounded.first() == rounded.last() {
+        rounded.pop();
+    }
+    Polyline {
+        points: rounded,
+        closed: outline.closed,
+    }
+}
+
+// The full pipeline used by the vector renderer: trace, inset into the walls, round the corners.
+pub fn maze_outlines(map: &Grid<u8>, inset: f32, radius: f32, segments: usize) -> Vec<Polyline> {
+    trace_wall_outlines(map)
+        .iter()
+        .map(|outline| round_corners(&inset_outline(outline, inset), radius, segments))
+        .collect()
+}
+
+// Renders outlines as an SVG document, `scale` pixels per tile.
+pub fn outlines_to_svg(
+    outlines: &[Polyline],
+    width: usize,
+    height: usize,
+    scale: f32,
+    stroke: &str,
+) -> String {
+    let mut svg = String::new();
+    let (w, h) = (width as f32 * scale, height as f32 * scale);
+    let _ = writeln!(
+        svg,
+        r#"<svg xmlns="http://www.w3.org/2000/svg" width="{w}" height="{h}" viewBox="0 0 {w} {h}">"#
+    );
+    let _ = writeln!(svg, r#"  <rect width="{w}" height="{h}" fill="black"/>"#);
+    for outline in outlines {
+        if outline.points.is_empty() {
+            continue;
+        }
+        let mut d = String::new();
+        for (i, p) in outline.points.iter().enumerate() {
+            let command = if i == 0 { 'M' } else { 'L' };
+            let _ = write!(d, "{}{} {} ", command, p.x * scale, p.y * scale);
+        }
+        if outline.closed {
+            d.push('Z');
+        }
+        let _ = writeln!(
+            svg,
+            r#"  <path d="{}" fill="none" stroke="{}" stroke-width="1"/>"#,
+            d.trim_end(),
+            stroke
+        );
+    }
+    svg.push_str("</svg>\n");
+    svg
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::load_level_from_string;
+
+    fn points(coords: &[(f32, f32)]) -> Vec<Vec2> {
+        coords.iter().map(|&(x, y)| Vec2::new(x, y)).collect()
+    }
+
+    // Twice the signed area: positive for outlines that run clockwise on screen (y down).
+    fn signed_area(outline: &Polyline) -> f32 {
+        let n = outline.points.len();
+        (0..n)
+            .map(|i| outline.points[i].perp_dot(outline.points[(i + 1) % n]))
+            .sum()
+    }
+
+    #[test]
+    fn single_block_is_one_clockwise_square() {
+        let map = load_level_from_string("   \n # \n   ");
+        let outlines = trace_wall_outlines(&map);
+        assert_eq!(
+            outlines,
+            vec![Polyline {
+                points: points(&[(1.0, 1.0), (2.0, 1.0), (2.0, 2.0), (1.0, 2.0)]),
+                closed: true,
+            }]
+        );
+        assert_eq!(signed_area(&outlines[0]), 2.0);
+    }
+
+    #[test]
+    fn ring_traces_outer_and_inner_loops_in_opposite_directions() {
+        let map = load_level_from_string("     \n ### \n # # \n ### \n     ");
+        let outlines = trace_wall_outlines(&map);
+        assert_eq!(outlines.len(), 2);
+        assert!(outlines.iter().all(|o| o.closed && o.points.len() == 4));
+
+        let mut areas: Vec<f32> = outlines.iter().map(signed_area).collect();
+        areas.sort_by(f32::total_cmp);
+        // The inner loop keeps the wall on its right, so it runs counter-clockwise.
+        assert_eq!(areas, vec![-2.0, 18.0]);
+    }
+
+    #[test]
+    fn door_notch_joins_the_house_into_one_outline() {
+        let map = load_level_from_string("       \n ##-## \n #   # \n ##### \n       ");
+        let outlines = trace_wall_outlines(&map);
+        assert_eq!(
+            outlines,
+            vec![Polyline {
+                points: points(&[
+                    (1.0, 1.0),
+                    (3.0, 1.0),
+                    (3.0, 2.0),
+                    (2.0, 2.0),
+                    (2.0, 3.0),
+                    (5.0, 3.0),
+                    (5.0, 2.0),
+                    (4.0, 2.0),
+                    (4.0, 1.0),
+                    (6.0, 1.0),
+                    (6.0, 4.0),
+                    (1.0, 4.0),
+                ]),
+                closed: true,
+            }]
+        );
+    }
+
+    #[test]
+    fn blob_touching_the_map_edge_is_left_open() {
+        let map = load_level_from_string("##  \n##  \n    ");
+        let outlines = trace_wall_outlines(&map);
+        assert_eq!(
+            outlines,
+            vec![Polyline {
+                points: points(&[(2.0, 0.0), (2.0, 2.0), (0.0, 2.0)]),
+                closed: false,
+            }]
+        );
+    }
+
+    #[test]
+    fn inset_moves_corners_into_the_wall() {
+        let square = Polyline {
+            points: points(&[(1.0, 1.0), (2.0, 1.0), (2.0, 2.0), (1.0, 2.0)]),
+            closed: true,
+        };
+        assert_eq!(
+            inset_outline(&square, 0.25).points,
+            points(&[(1.25, 1.25), (1.75, 1.25), (1.75, 1.75), (1.25, 1.75)])
+        );
+
+        // Open ends only move along their single edge.
+        let open = Polyline {
+            points: points(&[(2.0, 0.0), (2.0, 2.0), (0.0, 2.0)]),
+            closed: false,
+        };
+        assert_eq!(
+            inset_outline(&open, 0.25).points,
+            points(&[(1.75, 0.0), (1.75, 1.75), (0.0, 1.75)])
+        );
+    }
+
+    #[test]
+    fn rounding_cuts_corners_and_keeps_open_ends() {
+        let open = Polyline {
+            points: points(&[(0.0, 0.0), (4.0, 0.0), (4.0, 4.0)]),
+            closed: false,
+        };
+        let rounded = round_corners(&open, 1.0, 2);
+        assert_eq!(
+            rounded.points,
+            points(&[(0.0, 0.0), (3.0, 0.0), (3.75, 0.25), (4.0, 1.0), (4.0, 4.0)])
+        );
+        assert_eq!(round_corners(&open, 0.0, 2), open);
+        assert_eq!(round_corners(&open, 1.0, 0), open);
+    }
+
+    #[test]
+    fn rounding_clamps_to_half_an_edge_without_duplicate_points() {
+        let square = Polyline {
+            points: points(&[(0.0, 0.0), (1.0, 0.0), (1.0, 1.0), (0.0, 1.0)]),
+            closed: true,
+        };
+        let rounded = round_corners(&square, 5.0, 2);
+        // Each corner contributes a midpoint and a curve point; edge midpoints are shared.
+        assert_eq!(rounded.points.len(), 8);
+        assert!(rounded.points.contains(&Vec2::new(0.5, 0.0)));
+        assert!(!rounded.points.contains(&Vec2::new(1.0, 0.0)));
+        let n = rounded.points.len();
+        for i in 0..n {
+            assert_ne!(rounded.points[i], rounded.points[(i + 1) % n]);
+        }
+    }
+
+    #[test]
+    fn svg_has_one_path_per_outline() {
+        let outlines = vec![
+            Polyline {
+                points: points(&[(1.0, 1.0), (2.0, 1.0), (2.0, 2.0), (1.0, 2.0)]),
+                closed: true,
+            },
+            Polyline {
+                points: points(&[(2.0, 0.0), (2.0, 2.0)]),
+                closed: false,
+            },
+            Polyline {
+                points: Vec::new(),
+                closed: true,
+            },
+        ];
+        let svg = outlines_to_svg(&outlines, 3, 3, 8.0, "blue");
+        assert!(svg.starts_with(
+            r#"<svg xmlns="http://www.w3.org/2000/svg" width="24" height="24" viewBox="0 0 24 24">"#
+        ));
+        assert!(svg.ends_with("</svg>\n"));
+        assert_eq!(svg.matches("<path").count(), 2);
+        assert!(svg.contains(r#"d="M8 8 L16 8 L16 16 L8 16 Z" fill="none" stroke="blue""#));
+        assert!(svg.contains(r#"d="M16 0 L16 16" "#));
+    }
+}
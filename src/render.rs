@@ -0,0 +1,159 @@
+use crate::atlas::SpriteAtlas;
+use crate::autotile::WallAutotiler;
+use crate::tileset::WallTileset;
+use crate::{Grid, TILE_SIZE};
+use ggez::glam::Vec2;
+use ggez::graphics::{self, Canvas, Color, DrawParam, Image, InstanceArray, Rect};
+use ggez::{Context, GameResult};
+
+// Draws the maze without per-tile draw calls every frame: static walls are rendered once
+// into an offscreen layer and dots go through a single `InstanceArray`. Both are only
+// rebuilt after `invalidate_walls` / `invalidate_dots`, so drawing cost doesn't scale
+// with the map size.
+pub struct MazeRenderer {
+    layer: Image,        // Cached wall layer, `layer_scale` pixels per canvas pixel.
+    layer_scale: f32,    // Resolution multiplier so vector walls stay smooth when upscaled.
+    dots: InstanceArray, // One instance per dot, drawn from the atlas.
+    walls_dirty: bool,
+    dots_dirty: bool,
+}
+
+impl MazeRenderer {
+    pub fn new(
+        ctx: &mut Context,
+        atlas: &SpriteAtlas,
+        map: &Grid<u8>,
+        layer_scale: f32,
+    ) -> MazeRenderer {
+        MazeRenderer {
+            layer: Self::create_layer(ctx, map, layer_scale),
+            layer_scale,
+            dots: InstanceArray::new(ctx, atlas.image().clone()),
+            walls_dirty: true,
+            dots_dirty: true,
+        }
+    }
+
+    fn create_layer(ctx: &mut Context, map: &Grid<u8>, layer_scale: f32) -> Image {
+        let (width, height) = Self::layer_size(map, layer_scale);
+        Image::new_canvas_image(ctx, ctx.gfx.surface_format(), width, height, 1)
+    }
+
+    fn layer_size(map: &Grid<u8>, layer_scale: f32) -> (u32, u32) {
+        let width = (map.width() as f32 * TILE_SIZE * layer_scale).max(1.0) as u32;
+        let height = (map.height() as f32 * TILE_SIZE * layer_scale).max(1.0) as u32;
+        (width, height)
+    }
+
+    // Changes the wall layer's resolution, e.g. to the window's scale rounded up so the
+    // walls stay sharp at any window size. The layer is re-created and re-rendered on the
+    // next `update_walls`.
+    pub fn set_layer_scale(&mut self, layer_scale: f32) {
+        let layer_scale = layer_scale.max(1.0);
+        if layer_scale != self.layer_scale {
+            self.layer_scale = layer_scale;
+            self.walls_dirty = true;
+        }
+    }
+
+    // Marks the wall layer for re-rendering, e.g. after an edit or a style change.
+    pub fn invalidate_walls(&mut self) {
+        self.walls_dirty = true;
+    }
+
+    // Marks the dot instances for rebuilding, e.g. after a dot was eaten.
+    pub fn invalidate_dots(&mut self) {
+        self.dots_dirty = true;
+    }
+
+    // Re-renders the wall layer through `draw_walls` if it was invalidated.
+    // The callback draws in maze-local pixel coordinates, (0, 0) being the top-left tile.
+    pub fn update_walls(
+        &mut self,
+        ctx: &mut Context,
+        map: &Grid<u8>,
+        draw_walls: impl FnOnce(&mut Canvas) -> GameResult,
+    ) -> GameResult {
+        let layer_size = Self::layer_size(map, self.layer_scale);
+        if (self.layer.width(), self.layer.height()) != layer_size {
+            self.layer = Self::create_layer(ctx, map, self.layer_scale);
+            self.walls_dirty = true;
+        }
+        if !self.walls_dirty {
+            return Ok(());
+        }
+
+        let mut canvas =
+            Canvas::from_image(ctx, self.layer.clone(), Color::new(0.0, 0.0, 0.0, 0.0));
+        canvas.set_screen_coordinates(Rect::new(
+            0.0,
+            0.0,
+            map.width() as f32 * TILE_SIZE,
+            map.height() as f32 * TILE_SIZE,
+        ));
+        canvas.set_sampler(graphics::Sampler::nearest_clamp());
+        draw_walls(&mut canvas)?;
+        canvas.finish(ctx)?;
+
+        self.walls_dirty = false;
+        Ok(())
+    }
+
+    // Rebuilds the dot instances from the map if they were invalidated.
+    pub fn update_dots(&mut self, map: &Grid<u8>, small_dot_rect: Rect, big_dot_rect: Rect) {
+        if !self.dots_dirty {
+            return;
+        }
+        self.dots.set(map.iter().filter_map(|(x, y, &tile)| {
+            let src = match tile {
+                2 => small_dot_rect,
+                3 => big_dot_rect,
+                _ => return None,
+            };
+            let dest = Vec2::new(x as f32 * TILE_SIZE, y as f32 * TILE_SIZE);
+            Some(DrawParam::new().dest(dest).src(src))
+        }));
+        self.dots_dirty = false;
+    }
+
+    // Draws the cached walls and the dots with the maze's top-left corner at `origin`.
+    pub fn draw(&self, canvas: &mut Canvas, origin: Vec2) {
+        self.draw_walls(canvas, origin);
+        self.draw_dots(canvas, origin);
+    }
+
+    // Draws only the cached wall layer, e.g. to flash the walls at the end of a level.
+    pub fn draw_walls(&self, canvas: &mut Canvas, origin: Vec2) {
+        canvas.draw(
+            &self.layer,
+            DrawParam::new()
+                .dest(origin)
+                .scale(Vec2::splat(1.0 / self.layer_scale)),
+        );
+    }
+
+    // Draws only the dots.
+    pub fn draw_dots(&self, canvas: &mut Canvas, origin: Vec2) {
+        canvas.draw(&self.dots, DrawParam::new().dest(origin));
+    }
+}
+
+// Draws every wall tile of the map from the atlas, in maze-local pixel coordinates.
+// Meant to be called from a `MazeRenderer::update_walls` callback.
+pub fn draw_wall_tiles(
+    canvas: &mut Canvas,
+    atlas: &SpriteAtlas,
+    tileset: &WallTileset,
+    walls: &WallAutotiler,
+    map: &Grid<u8>,
+) {
+    for (x, y, _) in map.iter() {
+        if let Some(tile) = walls.tile_at(x, y) {
+            let dest = Vec2::new(x as f32 * TILE_SIZE, y as f32 * TILE_SIZE);
+            canvas.draw(
+                atlas.image(),
+                DrawParam::new().dest(dest).src(tileset.rect(tile)),
+            );
+        }
+    }
+}
@@ -0,0 +1,440 @@
+use crate::controller::Controller;
+use crate::sim::Simulation;
+use crate::state_hash::hash_of;
+use crate::{Direction, Grid};
+use ggez::{Context, GameError, GameResult};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::io::Read;
+use std::path::Path;
+
+// Version written to new replay files; files of other versions are refused.
+pub const REPLAY_VERSION: u32 = 1;
+
+// Ticks between the state hashes a recording keeps, one per second of play.
+pub const HASH_INTERVAL: u64 = 60;
+
+// A complete recorded run: what the player asked for on every tick, plus everything else
+// the simulation depends on, so it can be reproduced exactly. Stored as JSON:
+//
+//     {
+//       "version": 1,
+//       "seed": 3141592653,
+//       "level_hash": "5d1f5c40a2b3c4d5",
+//       "length": 4210,
+//       "inputs": "0W61N69E412S",
+//       "hashes": [[60, "a3f0c2d19e8b7765"], [120, "03b4e1f2c5d6a798"]]
+//     }
+//
+// `level_hash` identifies the level the run was played on, see `level_hash`. `length` is
+// the number of ticks run. `inputs` lists the ticks with a new request as the ticks
+// since the previous one followed by the direction's initial; all other ticks requested
+// nothing. `hashes` optionally holds `Simulation::state_hash` at some tick counts, taken
+// as the next tick was about to run, for checking that playback matches the recording.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct Replay {
+    pub seed: u32,
+    pub level_hash: u64,
+    pub length: u64,
+    inputs: Vec<(u64, Direction)>, // Sorted by tick, at most one entry per tick.
+    hashes: Vec<(u64, u64)>,       // Expected state hashes, sorted by tick.
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct ReplayDef {
+    version: u32,
+    seed: u32,
+    level_hash: String,
+    length: u64,
+    inputs: String,
+    #[serde(default)]
+    hashes: Vec<(u64, String)>,
+}
+
+// A stable hash of a level's size and tiles. A replay only reproduces on the exact level
+// it was recorded on.
+pub fn level_hash(map: &Grid<u8>) -> u64 {
+    hash_of(map)
+}
+
+fn direction_letter(direction: Direction) -> char {
+    match direction {
+        Direction::North => 'N',
+        Direction::East => 'E',
+        Direction::South => 'S',
+        Direction::West => 'W',
+        Direction::Stopped => '-',
+    }
+}
+
+fn letter_direction(letter: char) -> Option<Direction> {
+    match letter {
+        'N' => Some(Direction::North),
+        'E' => Some(Direction::East),
+        'S' => Some(Direction::South),
+        'W' => Some(Direction::West),
+        _ => None,
+    }
+}
+
+// Parses a replay file without touching the filesystem.
+pub fn parse_replay(content: &str) -> Result<Replay, String> {
+    let def: ReplayDef = serde_json::from_str(content).map_err(|e| e.to_string())?;
+    if def.version != REPLAY_VERSION {
+        return Err(format!(
+            "unsupported version {} (expected {})",
+            def.version, REPLAY_VERSION
+        ));
+    }
+    let level_hash = u64::from_str_radix(&def.level_hash, 16)
+        .map_err(|_| format!("invalid level hash {:?}", def.level_hash))?;
+
+    let mut inputs = Vec::new();
+    let mut tick = 0;
+    let mut gap = String::new();
+    for c in def.inputs.chars() {
+        if c.is_ascii_digit() {
+            gap.push(c);
+            continue;
+        }
+        let direction =
+            letter_direction(c).ok_or_else(|| format!("unknown direction {:?} in inputs", c))?;
+        let ticks: u64 = gap
+            .parse()
+            .map_err(|_| format!("missing tick count before {:?} in inputs", c))?;
+        if !inputs.is_empty() && ticks == 0 {
+            return Err("two inputs on the same tick".to_string());
+        }
+        tick += ticks;
+        inputs.push((tick, direction));
+        gap.clear();
+    }
+    if !gap.is_empty() {
+        return Err("inputs end in a tick count without a direction".to_string());
+    }
+    let mut hashes = Vec::with_capacity(def.hashes.len());
+    for (tick, hash) in def.hashes {
+        let hash = u64::from_str_radix(&hash, 16)
+            .map_err(|_| format!("tick {}: invalid state hash {:?}", tick, hash))?;
+        if hashes.last().is_some_and(|&(last, _)| last >= tick) {
+            return Err(format!(
+                "tick {}: hashes must be in increasing tick order",
+                tick
+            ));
+        }
+        hashes.push((tick, hash));
+    }
+    if inputs.last().is_some_and(|&(last, _)| last >= def.length) {
+        return Err(format!(
+            "inputs past the end of the replay at tick {}",
+            tick
+        ));
+    }
+    Ok(Replay {
+        seed: def.seed,
+        level_hash,
+        length: def.length,
+        inputs,
+        hashes,
+    })
+}
+
+// Serializes a replay to the format read by `parse_replay`.
+pub fn save_replay_to_string(replay: &Replay) -> String {
+    let mut inputs = String::new();
+    let mut last = 0;
+    for &(tick, direction) in &replay.inputs {
+        inputs.push_str(&(tick - last).to_string());
+        inputs.push(direction_letter(direction));
+        last = tick;
+    }
+    let def = ReplayDef {
+        version: REPLAY_VERSION,
+        seed: replay.seed,
+        level_hash: format!("{:016x}", replay.level_hash),
+        length: replay.length,
+        inputs,
+        hashes: replay
+            .hashes
+            .iter()
+            .map(|&(tick, hash)| (tick, format!("{:016x}", hash)))
+            .collect(),
+    };
+    serde_json::to_string(&def).expect("replays always serialize")
+}
+
+impl Replay {
+    // An empty recording of a game started with `seed` on `map`.
+    pub fn new(seed: u32, map: &Grid<u8>) -> Replay {
+        Replay {
+            seed,
+            level_hash: level_hash(map),
+            length: 0,
+            inputs: Vec::new(),
+            hashes: Vec::new(),
+        }
+    }
+
+    // Loads a replay shipped with the game, such as the attract-mode demo "/demo.json".
+    pub fn load(ctx: &mut Context, path: &str) -> GameResult<Replay> {
+        let mut content = String::new();
+        ctx.fs.open(path)?.read_to_string(&mut content)?;
+        parse_replay(&content)
+            .map_err(|e| GameError::ResourceLoadError(format!("Invalid replay {}: {}", path, e)))
+    }
+
+    // Reads a replay file from anywhere on disk, e.g. one sent in with a bug report.
+    pub fn read(path: &Path) -> GameResult<Replay> {
+        let content = fs::read_to_string(path)?;
+        parse_replay(&content)
+            .map_err(|e| GameError::ResourceLoadError(format!("Invalid replay {:?}: {}", path, e)))
+    }
+
+    pub fn write(&self, path: &Path) -> GameResult {
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        fs::write(path, save_replay_to_string(self))?;
+        Ok(())
+    }
+
+    // Adds the intent of the tick after `tick` ticks have run. A later intent for the
+    // same tick replaces the earlier one.
+    pub fn record(&mut self, tick: u64, intent: Direction) {
+        if let Some(&(last, _)) = self.inputs.last()
+            && last >= tick
+        {
+            self.inputs.retain(|&(t, _)| t < tick);
+        }
+        if intent != Direction::Stopped {
+            self.inputs.push((tick, intent));
+        }
+        self.length = self.length.max(tick + 1);
+    }
+
+    // Adds the state hash after `tick` ticks, replacing any later ones.
+    pub fn record_hash(&mut self, tick: u64, hash: u64) {
+        self.hashes.retain(|&(t, _)| t < tick);
+        self.hashes.push((tick, hash));
+    }
+
+    // The state hash recorded after `tick` ticks, if one was.
+    pub fn expected_hash(&self, tick: u64) -> Option<u64> {
+        self.hashes
+            .binary_search_by_key(&tick, |&(t, _)| t)
+            .ok()
+            .map(|i| self.hashes[i].1)
+    }
+
+    // The recorded intent for the tick after `tick` ticks have run.
+    pub fn intent_at(&self, tick: u64) -> Direction {
+        match self.inputs.binary_search_by_key(&tick, |&(t, _)| t) {
+            Ok(i) => self.inputs[i].1,
+            Err(_) => Direction::Stopped,
+        }
+    }
+
+    pub fn is_finished(&self, tick: u64) -> bool {
+        tick >= self.length
+    }
+
+    // The simulation to play the replay back on: a fresh game on `map` with the recorded
+    // seed. Fails if `map` isn't the level the replay was recorded on.
+    pub fn start(&self, map: Grid<u8>) -> Result<Simulation, String> {
+        let hash = level_hash(&map);
+        if hash != self.level_hash {
+            return Err(format!(
+                "recorded on level {:016x}, not {:016x}",
+                self.level_hash, hash
+            ));
+        }
+        Ok(Simulation::new(map, self.seed))
+    }
+}
+
+// Plays a replay back into a simulation returned by `Replay::start`.
+impl Controller for Replay {
+    fn intent(&mut self, sim: &Simulation) -> Direction {
+        self.intent_at(sim.tick_count())
+    }
+}
+
+// Wraps another controller and records every intent it hands out.
+#[derive(Debug, Clone)]
+pub struct Recorder<C> {
+    pub inner: C,
+    replay: Replay,
+}
+
+impl<C: Controller> Recorder<C> {
+    pub fn new(inner: C, replay: Replay) -> Recorder<C> {
+        Recorder { inner, replay }
+    }
+
+    pub fn replay(&self) -> &Replay {
+        &self.replay
+    }
+
+    // Records the final state hash once the run is over.
+    pub fn finish(&mut self, sim: &Simulation) {
+        self.replay.record_hash(sim.tick_count(), sim.state_hash());
+    }
+}
+
+impl<C: Controller> Controller for Recorder<C> {
+    fn intent(&mut self, sim: &Simulation) -> Direction {
+        let tick = sim.tick_count();
+        if tick > 0 && tick.is_multiple_of(HASH_INTERVAL) {
+            self.replay.record_hash(tick, sim.state_hash());
+        }
+        let intent = self.inner.intent(sim);
+        // Asking again for the direction already requested changes nothing, so it is
+        // left out to keep the file small; bots and held sticks do it every tick.
+        let recorded = if intent == sim.player.desired_direction {
+            Direction::Stopped
+        } else {
+            intent
+        };
+        self.replay.record(tick, recorded);
+        intent
+    }
+}
+
+// Runs `sim` with `controller` the way the game does, through deaths and cleared levels,
+// until the game is over or `max_ticks` ticks have run. `on_tick` sees the simulation
+// after every tick, once a lost life or cleared level has been dealt with, i.e. in the
+// state the next tick starts from. Returns the number of lives lost.
+pub fn play(
+    sim: &mut Simulation,
+    controller: &mut dyn Controller,
+    max_ticks: u64,
+    mut on_tick: impl FnMut(&Simulation),
+) -> u32 {
+    let mut deaths = 0;
+    while sim.tick_count() < max_ticks {
+        sim.step(controller);
+        let caught = sim.is_player_caught();
+        let game_over = caught && !sim.lose_life();
+        if sim.is_level_cleared() {
+            sim.next_level();
+        }
+        deaths += caught as u32;
+        on_tick(sim);
+        if game_over {
+            break;
+        }
+    }
+    deaths
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample() -> Replay {
+        let mut replay = Replay::new(7, &Grid::new(3, 2, 1));
+        replay.record(0, Direction::West);
+        replay.record(1, Direction::Stopped);
+        replay.record(12, Direction::North);
+        replay.record(130, Direction::South);
+        replay.record_hash(60, 0x0123_4567_89ab_cdef);
+        replay.record_hash(120, 0xfedc_ba98_7654_3210);
+        replay.record(199, Direction::Stopped);
+        replay
+    }
+
+    #[test]
+    fn replay_round_trips() {
+        let replay = sample();
+        let saved = save_replay_to_string(&replay);
+        assert!(saved.contains(r#""inputs":"0W12N118S""#));
+        assert!(saved.contains(r#""length":200"#));
+        assert_eq!(parse_replay(&saved), Ok(replay));
+    }
+
+    #[test]
+    fn empty_replay_round_trips() {
+        let replay = Replay::new(0, &Grid::new(1, 1, 0));
+        assert_eq!(parse_replay(&save_replay_to_string(&replay)), Ok(replay));
+    }
+
+    #[test]
+    fn later_records_replace_the_rest_of_the_run() {
+        let mut replay = sample();
+        replay.record(12, Direction::East);
+        replay.record_hash(60, 1);
+        assert_eq!(replay.intent_at(0), Direction::West);
+        assert_eq!(replay.intent_at(12), Direction::East);
+        assert_eq!(replay.intent_at(130), Direction::Stopped);
+        assert_eq!(replay.expected_hash(60), Some(1));
+        assert_eq!(replay.expected_hash(120), None);
+    }
+
+    #[test]
+    fn hashes_are_optional() {
+        let replay =
+            parse_replay(r#"{"version":1,"seed":1,"level_hash":"ff","length":10,"inputs":"3E"}"#)
+                .unwrap();
+        assert_eq!(replay.level_hash, 0xff);
+        assert_eq!(replay.intent_at(3), Direction::East);
+        assert_eq!(replay.expected_hash(3), None);
+    }
+
+    #[test]
+    fn invalid_replays_are_rejected() {
+        let with = |field: &str| {
+            let mut def = serde_json::json!({
+                "version": 1,
+                "seed": 1,
+                "level_hash": "ff",
+                "length": 10,
+                "inputs": "3E",
+            });
+            let (key, value) = field.split_once('=').unwrap();
+            def[key] = serde_json::from_str(value).unwrap();
+            parse_replay(&def.to_string())
+        };
+        assert!(
+            with("version=2")
+                .unwrap_err()
+                .contains("unsupported version")
+        );
+        assert!(
+            with(r#"level_hash="xyz""#)
+                .unwrap_err()
+                .contains("level hash")
+        );
+        assert!(
+            with(r#"inputs="3Q""#)
+                .unwrap_err()
+                .contains("unknown direction")
+        );
+        assert!(
+            with(r#"inputs="E""#)
+                .unwrap_err()
+                .contains("missing tick count")
+        );
+        assert!(with(r#"inputs="3E0N""#).unwrap_err().contains("same tick"));
+        assert!(
+            with(r#"inputs="3E4""#)
+                .unwrap_err()
+                .contains("tick count without")
+        );
+        assert!(
+            with(r#"inputs="3E7N""#)
+                .unwrap_err()
+                .contains("past the end")
+        );
+        assert!(
+            with(r#"hashes=[[60,"1"],[60,"2"]]"#)
+                .unwrap_err()
+                .contains("increasing tick order")
+        );
+        assert!(
+            with(r#"hashes=[[60,"zz"]]"#)
+                .unwrap_err()
+                .contains("invalid state hash")
+        );
+        assert!(parse_replay("not json").is_err());
+    }
+}
@@ -0,0 +1,42 @@
+use crate::state_hash::{HashState, StateHasher};
+
+// A small xorshift random number generator. The simulation owns one so that a run is
+// fully determined by its seed and inputs, independent of platform or library versions.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Rng {
+    state: u32,
+}
+
+impl Rng {
+    // Creates a generator from a seed. A zero seed is remapped, since xorshift would stay at zero.
+    pub fn new(seed: u32) -> Rng {
+        Rng {
+            state: if seed == 0 { 0x9E37_79B9 } else { seed },
+        }
+    }
+
+    // The internal state, e.g. for hashing the simulation.
+    pub fn state(&self) -> u32 {
+        self.state
+    }
+
+    pub fn next_u32(&mut self) -> u32 {
+        let mut x = self.state;
+        x ^= x << 13;
+        x ^= x >> 17;
+        x ^= x << 5;
+        self.state = x;
+        x
+    }
+
+    // A number in `0..n`, or 0 if `n` is 0.
+    pub fn below(&mut self, n: u32) -> u32 {
+        if n == 0 { 0 } else { self.next_u32() % n }
+    }
+}
+
+impl HashState for Rng {
+    fn hash_state(&self, hasher: &mut StateHasher) {
+        self.state.hash_state(hasher);
+    }
+}
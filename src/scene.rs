@@ -0,0 +1,103 @@
+use crate::bindings::Action;
+use ggez::Context;
+use ggez::graphics::Canvas;
+
+// What the scene stack should do after a scene handled an update or an action.
+pub enum Transition<W> {
+    None,                       // Stay on the current scene.
+    Push(Box<dyn Scene<W>>),    // Put a scene on top, e.g. a pause overlay.
+    Pop,                        // Remove the top scene and return to the one below.
+    Replace(Box<dyn Scene<W>>), // Swap the top scene for another.
+    Reset(Box<dyn Scene<W>>),   // Drop every scene and start over with this one.
+}
+
+// One screen of a frontend, e.g. the title screen or a pause overlay. `W` is the state
+// shared by all scenes (assets, the simulation, settings), owned by the frontend.
+pub trait Scene<W> {
+    // Advances the scene by one fixed tick. Only the top scene is updated.
+    fn update(&mut self, world: &mut W, ctx: &mut Context) -> Transition<W>;
+
+    // Draws the scene. Overlays are drawn on top of the scenes below them.
+    fn draw(&self, world: &W, canvas: &mut Canvas);
+
+    // Handles an action triggered by the player, e.g. a bound key press. Only the top
+    // scene receives input.
+    fn action(&mut self, _world: &mut W, _ctx: &mut Context, _action: Action) -> Transition<W> {
+        Transition::None
+    }
+
+    // True for scenes that only cover part of the screen, so the scene below stays visible.
+    fn is_overlay(&self) -> bool {
+        false
+    }
+}
+
+// A stack of scenes. The top one runs and receives input; it can push another scene on
+// top (which returns to it when popped) or replace itself, so every screen only knows the
+// screens it leads to.
+pub struct SceneStack<W> {
+    scenes: Vec<Box<dyn Scene<W>>>,
+}
+
+impl<W> SceneStack<W> {
+    pub fn new(initial: Box<dyn Scene<W>>) -> SceneStack<W> {
+        SceneStack {
+            scenes: vec![initial],
+        }
+    }
+
+    // True once the last scene was popped; the frontend should quit.
+    pub fn is_empty(&self) -> bool {
+        self.scenes.is_empty()
+    }
+
+    pub fn len(&self) -> usize {
+        self.scenes.len()
+    }
+
+    pub fn apply(&mut self, transition: Transition<W>) {
+        match transition {
+            Transition::None => {}
+            Transition::Push(scene) => self.scenes.push(scene),
+            Transition::Pop => {
+                self.scenes.pop();
+            }
+            Transition::Replace(scene) => {
+                self.scenes.pop();
+                self.scenes.push(scene);
+            }
+            Transition::Reset(scene) => {
+                self.scenes.clear();
+                self.scenes.push(scene);
+            }
+        }
+    }
+
+    // Updates the top scene and applies the transition it asks for.
+    pub fn update(&mut self, world: &mut W, ctx: &mut Context) {
+        if let Some(scene) = self.scenes.last_mut() {
+            let transition = scene.update(world, ctx);
+            self.apply(transition);
+        }
+    }
+
+    // Passes an action to the top scene and applies the transition it asks for.
+    pub fn action(&mut self, world: &mut W, ctx: &mut Context, action: Action) {
+        if let Some(scene) = self.scenes.last_mut() {
+            let transition = scene.action(world, ctx, action);
+            self.apply(transition);
+        }
+    }
+
+    // Draws the top scene, preceded by the scenes below it as long as it is an overlay.
+    pub fn draw(&self, world: &W, canvas: &mut Canvas) {
+        let first = self
+            .scenes
+            .iter()
+            .rposition(|scene| !scene.is_overlay())
+            .unwrap_or(0);
+        for scene in &self.scenes[first..] {
+            scene.draw(world, canvas);
+        }
+    }
+}
@@ -0,0 +1,95 @@
+use crate::fruit::Fruit;
+use crate::state_hash::{HashState, StateHasher};
+
+// Points per item.
+pub const DOT_POINTS: u32 = 10;
+pub const BIG_DOT_POINTS: u32 = 50;
+
+// Score at which the player earns the one extra life of the arcade default settings.
+pub const EXTRA_LIFE_SCORE: u32 = 10_000;
+
+// Lives at the start of a game, including the one in play.
+pub const STARTING_LIVES: u32 = 3;
+
+// Scores and progress of a one- or two-player game, everything the HUD shows.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Scoreboard {
+    pub scores: [u32; 2],      // Score of player 1 and player 2.
+    pub high_score: u32,       // Best score seen, including the running game.
+    pub player_count: usize,   // 1 or 2.
+    pub current_player: usize, // Index into `scores` of the player in play.
+    pub lives: u32,            // Lives of the current player, including the one in play.
+    pub level: u32,            // Current level, starting at 1.
+    extra_life_awarded: bool,
+}
+
+impl Scoreboard {
+    pub fn new(player_count: usize, high_score: u32) -> Scoreboard {
+        Scoreboard {
+            scores: [0; 2],
+            high_score,
+            player_count: player_count.clamp(1, 2),
+            current_player: 0,
+            lives: STARTING_LIVES,
+            level: 1,
+            extra_life_awarded: false,
+        }
+    }
+
+    pub fn score(&self) -> u32 {
+        self.scores[self.current_player]
+    }
+
+    // Adds points to the current player, raising the high score along with it.
+    // Returns true if this crossed `EXTRA_LIFE_SCORE` and earned an extra life.
+    pub fn add_points(&mut self, points: u32) -> bool {
+        let score = &mut self.scores[self.current_player];
+        *score += points;
+        self.high_score = self.high_score.max(*score);
+        if !self.extra_life_awarded && *score >= EXTRA_LIFE_SCORE {
+            self.extra_life_awarded = true;
+            self.lives += 1;
+            return true;
+        }
+        false
+    }
+
+    // The fruits of the levels reached so far, oldest first, at most `count` of them.
+    pub fn recent_fruits(&self, count: usize) -> Vec<Fruit> {
+        if count == 0 {
+            return Vec::new();
+        }
+        let first = self.level.saturating_sub(count as u32 - 1).max(1);
+        (first..=self.level).map(Fruit::for_level).collect()
+    }
+}
+
+impl HashState for Scoreboard {
+    fn hash_state(&self, hasher: &mut StateHasher) {
+        // The high score carries over from earlier games, so it is no part of this run.
+        self.scores[..].hash_state(hasher);
+        self.player_count.hash_state(hasher);
+        self.current_player.hash_state(hasher);
+        self.lives.hash_state(hasher);
+        self.level.hash_state(hasher);
+        self.extra_life_awarded.hash_state(hasher);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn recent_fruits_are_the_last_levels_reached() {
+        let mut scoreboard = Scoreboard::new(1, 0);
+        assert_eq!(scoreboard.recent_fruits(7), [Fruit::Cherry]);
+        scoreboard.level = 5;
+        assert_eq!(
+            scoreboard.recent_fruits(3),
+            [Fruit::Orange, Fruit::Orange, Fruit::Apple]
+        );
+        assert_eq!(scoreboard.recent_fruits(7).len(), 5);
+        assert_eq!(scoreboard.recent_fruits(0), []);
+    }
+}
@@ -0,0 +1,1046 @@
+use crate::controller::Controller;
+use crate::events::GameEvent;
+use crate::fruit::Fruit;
+use crate::ghost::GhostMode;
+use crate::rng::Rng;
+use crate::score::{self, Scoreboard};
+use crate::state_hash::{HashState, StateFields, StateHasher};
+use crate::{
+    Direction, GHOST_STARTS, Grid, MAZE_OFFSET_Y, PLAYER_SPEED, PLAYER_START, TILE_SIZE,
+    is_rect_walkable, tile_at_pixel,
+};
+use ggez::glam::Vec2;
+use ggez::graphics::Rect;
+
+// The simulation advances in fixed steps so a run only depends on its inputs, not on the frame rate.
+pub const TICK_RATE: u32 = 60;
+pub const TICK_SECONDS: f32 = 1.0 / TICK_RATE as f32;
+
+// Ghost speeds in pixels per second.
+pub const GHOST_SPEED: f32 = 38.0;
+pub const FRIGHTENED_GHOST_SPEED: f32 = 24.0;
+pub const EYES_SPEED: f32 = 80.0;
+pub const HOUSE_GHOST_SPEED: f32 = 20.0;
+
+// Ghost house tiles, matching the house the editor enforces: the door ghosts leave
+// through, the tile inside that eaten ghosts return to, and the inclusive corners of the
+// area behind the door that only ghosts leaving the house and eyes may enter.
+pub const HOUSE_DOOR: (isize, isize) = (13, 11);
+pub const HOUSE_CENTER: (isize, isize) = (13, 14);
+const HOUSE_AREA: ((isize, isize), (isize, isize)) = ((11, 12), (16, 15));
+
+// Alternating scatter and chase phases in seconds, starting with scatter.
+// After the last one the ghosts chase for the rest of the level.
+const PHASE_SCHEDULE: [f32; 7] = [7.0, 20.0, 7.0, 20.0, 5.0, 20.0, 5.0];
+
+// Seconds after a (re)start before each ghost leaves the house, in `GHOST_STARTS` order.
+const GHOST_RELEASE_SECONDS: [f32; 4] = [0.0, 0.0, 4.0, 8.0];
+
+// Points for the first ghost eaten on one power pellet; each further one doubles it.
+pub const GHOST_POINTS: u32 = 200;
+
+// The bonus fruit appears below the ghost house after this many dots of a level are eaten,
+// and leaves again if it isn't eaten in time.
+const FRUIT_DOTS_EATEN: [usize; 2] = [70, 170];
+const FRUIT_SECONDS: f32 = 9.5;
+pub const FRUIT_TILE: (isize, isize) = (13, 17);
+
+// Whether the ghosts are heading for their corners or hunting the player.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GhostPhase {
+    Scatter,
+    Chase,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct PlayerState {
+    pub pos: Vec2,                    // Center of the player in screen pixels.
+    pub direction: Direction,         // Current movement direction.
+    pub desired_direction: Direction, // Last requested direction, taken as soon as it is free.
+    pub facing: Direction,            // Last direction moved in; never `Stopped`.
+}
+
+impl PlayerState {
+    fn at_start() -> PlayerState {
+        PlayerState {
+            pos: Vec2::new(
+                PLAYER_START.0 * TILE_SIZE,
+                PLAYER_START.1 * TILE_SIZE + MAZE_OFFSET_Y,
+            ),
+            direction: Direction::Stopped,
+            desired_direction: Direction::Stopped,
+            facing: Direction::West,
+        }
+    }
+
+    // The rect checked against walls, one tile centered on `pos`.
+    pub fn collision_rect(&self) -> Rect {
+        collision_rect_at(self.pos)
+    }
+
+    // Cause before effect, so the input that made a run go differently is the field
+    // reported rather than the movement it led to.
+    fn add_fields(&self, fields: &mut StateFields) {
+        fields.add("player.desired_direction", &self.desired_direction);
+        fields.add("player.direction", &self.direction);
+        fields.add("player.pos", &self.pos);
+        fields.add("player.facing", &self.facing);
+    }
+}
+
+fn collision_rect_at(pos: Vec2) -> Rect {
+    Rect::new(
+        pos.x - TILE_SIZE / 2.0,
+        pos.y - TILE_SIZE / 2.0,
+        TILE_SIZE,
+        TILE_SIZE,
+    )
+}
+
+// The bonus fruit while it waits at `FRUIT_TILE`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct FruitState {
+    pub fruit: Fruit,
+    pub time_left: f32, // Seconds before it disappears uneaten.
+}
+
+impl HashState for FruitState {
+    fn hash_state(&self, hasher: &mut StateHasher) {
+        self.fruit.hash_state(hasher);
+        self.time_left.hash_state(hasher);
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct GhostState {
+    pub name: &'static str, // Animation actor name, which also selects the chase behavior.
+    pub pos: Vec2,          // Center of the ghost in screen pixels.
+    pub direction: Direction, // Current heading.
+    pub mode: GhostMode,    // Normal, frightened or eyes.
+    pub in_house: bool,     // Waiting in or leaving the ghost house.
+    pub release_time: f32,  // Seconds left before leaving the house.
+    pub target: (isize, isize), // Tile the ghost is steering towards.
+    scatter_target: (isize, isize),
+    decided_at: Option<(isize, isize)>, // Tile whose center the current heading was chosen at.
+}
+
+impl GhostState {
+    pub fn tile(&self) -> (isize, isize) {
+        tile_at_pixel(self.pos.x, self.pos.y)
+    }
+
+    fn speed(&self) -> f32 {
+        match self.mode {
+            GhostMode::Eyes => EYES_SPEED,
+            GhostMode::Frightened { .. } => FRIGHTENED_GHOST_SPEED,
+            GhostMode::Normal if self.in_house => HOUSE_GHOST_SPEED,
+            GhostMode::Normal => GHOST_SPEED,
+        }
+    }
+
+    // Turns around on the spot; the next tile center picks a fresh heading.
+    fn reverse(&mut self) {
+        self.direction = self.direction.opposite();
+        self.decided_at = None;
+    }
+
+    // Fields named after the ghost ("ghosts.inky.pos"), so a report says which one drifted.
+    fn add_fields(&self, fields: &mut StateFields) {
+        let prefix = format!("ghosts.{}", self.name);
+        fields.add(format!("{}.pos", prefix), &self.pos);
+        fields.add(format!("{}.direction", prefix), &self.direction);
+        fields.add(format!("{}.mode", prefix), &self.mode);
+        fields.add(format!("{}.in_house", prefix), &self.in_house);
+        fields.add(format!("{}.release_time", prefix), &self.release_time);
+        fields.add(format!("{}.target", prefix), &self.target);
+        fields.add(format!("{}.scatter_target", prefix), &self.scatter_target);
+        fields.add(format!("{}.decided_at", prefix), &self.decided_at);
+    }
+}
+
+// The game rules without any rendering or input handling: the maze, the player, the
+// ghosts and the score, advanced one fixed step at a time by `tick`. Frontends feed it
+// the player's intent, usually from a `Controller` passed to `step`, and draw whatever
+// state it ends up in. What happened on a tick is reported as `GameEvent`s, see
+// `drain_events`.
+#[derive(Debug, Clone)]
+pub struct Simulation {
+    pub map: Grid<u8>,
+    initial_map: Grid<u8>, // The level as loaded, restored for every new level.
+    pub player: PlayerState,
+    pub ghosts: Vec<GhostState>,
+    pub fruit: Option<FruitState>,
+    pub scoreboard: Scoreboard,
+    tick: u64,
+    phase_index: usize, // Index into `PHASE_SCHEDULE`; past the end means endless chase.
+    phase_time: f32,    // Seconds spent in the current phase.
+    ghost_combo: u32,   // Ghosts eaten on the current power pellet.
+    dots_left: usize,
+    dot_count: usize, // Dots at the start of every level, counted once from `initial_map`.
+    rng: Rng,
+    player_caught: bool,
+    events: Vec<GameEvent>, // Events of the last tick; not part of the state.
+}
+
+impl Simulation {
+    // Starts a one-player game on `map`. `seed` drives the frightened ghosts' random turns.
+    pub fn new(map: Grid<u8>, seed: u32) -> Simulation {
+        let dot_count = count_dots(&map);
+        let mut sim = Simulation {
+            initial_map: map.clone(),
+            map,
+            player: PlayerState::at_start(),
+            ghosts: Vec::new(),
+            fruit: None,
+            scoreboard: Scoreboard::new(1, 0),
+            tick: 0,
+            phase_index: 0,
+            phase_time: 0.0,
+            ghost_combo: 0,
+            dots_left: dot_count,
+            dot_count,
+            rng: Rng::new(seed),
+            player_caught: false,
+            events: Vec::new(),
+        };
+        sim.reset_actors();
+        sim
+    }
+
+    // A hash of the whole state, equal for two runs exactly when they are in the same
+    // state, on any machine. Replays store it to detect a run going differently.
+    pub fn state_hash(&self) -> u64 {
+        self.state_fields().hash()
+    }
+
+    // The whole state split into named fields, each hashed on its own, for finding out
+    // where two runs went apart. `state_hash` combines them.
+    pub fn state_fields(&self) -> StateFields {
+        let mut fields = StateFields::new();
+        fields.add("tick", &self.tick);
+        fields.add("map", &self.map);
+        fields.add("initial_map", &self.initial_map);
+        fields.add("dots_left", &self.dots_left);
+        self.player.add_fields(&mut fields);
+        fields.add("player_caught", &self.player_caught);
+        fields.add("ghosts.count", &self.ghosts.len());
+        for ghost in &self.ghosts {
+            ghost.add_fields(&mut fields);
+        }
+        fields.add("phase_index", &self.phase_index);
+        fields.add("phase_time", &self.phase_time);
+        fields.add("ghost_combo", &self.ghost_combo);
+        fields.add("fruit", &self.fruit);
+        fields.add("scoreboard", &self.scoreboard);
+        fields.add("rng", &self.rng);
+        fields
+    }
+
+    // Number of ticks simulated so far.
+    pub fn tick_count(&self) -> u64 {
+        self.tick
+    }
+
+    pub fn dots_left(&self) -> usize {
+        self.dots_left
+    }
+
+    // Dots in the full maze at the start of a level.
+    pub fn dot_count(&self) -> usize {
+        self.dot_count
+    }
+
+    pub fn rng(&self) -> &Rng {
+        &self.rng
+    }
+
+    // Takes the events of the last tick, oldest first. Each tick starts with an empty
+    // list, so events not drained before the next tick are dropped.
+    pub fn drain_events(&mut self) -> std::vec::Drain<'_, GameEvent> {
+        self.events.drain(..)
+    }
+
+    // True once a ghost caught the player. The simulation stands still until `reset_actors`.
+    pub fn is_player_caught(&self) -> bool {
+        self.player_caught
+    }
+
+    // True once every dot is eaten. The simulation stands still until `next_level`.
+    pub fn is_level_cleared(&self) -> bool {
+        self.dots_left == 0
+    }
+
+    pub fn phase(&self) -> GhostPhase {
+        if self.phase_index.is_multiple_of(2) && self.phase_index < PHASE_SCHEDULE.len() {
+            GhostPhase::Scatter
+        } else {
+            GhostPhase::Chase
+        }
+    }
+
+    // Seconds until the next scatter/chase switch, or `None` during the final chase.
+    pub fn phase_time_left(&self) -> Option<f32> {
+        PHASE_SCHEDULE
+            .get(self.phase_index)
+            .map(|length| length - self.phase_time)
+    }
+
+    // Puts the player and the ghosts back at their starting points, e.g. after a death.
+    // Dots and score stay as they are.
+    pub fn reset_actors(&mut self) {
+        self.player = PlayerState::at_start();
+        let (width, height) = (self.map.width() as isize, self.map.height() as isize);
+        let scatter_targets = [
+            (width - 3, -3),
+            (2, -3),
+            (width - 1, height + 1),
+            (0, height + 1),
+        ];
+        self.ghosts = GHOST_STARTS
+            .iter()
+            .zip(scatter_targets)
+            .zip(GHOST_RELEASE_SECONDS)
+            .map(
+                |((&(name, (x, y), direction), scatter_target), release_time)| {
+                    let pos = Vec2::new(x * TILE_SIZE, y * TILE_SIZE + MAZE_OFFSET_Y);
+                    let tile = tile_at_pixel(pos.x, pos.y);
+                    GhostState {
+                        name,
+                        pos,
+                        direction,
+                        mode: GhostMode::Normal,
+                        // Ghosts starting on the door are already out, like Blinky in the arcade.
+                        in_house: tile != HOUSE_DOOR,
+                        release_time,
+                        target: tile,
+                        scatter_target,
+                        decided_at: None,
+                    }
+                },
+            )
+            .collect();
+        self.phase_index = 0;
+        self.phase_time = 0.0;
+        self.ghost_combo = 0;
+        self.fruit = None;
+        self.player_caught = false;
+    }
+
+    // Takes a life after the player was caught and puts the actors back for the next one.
+    // Returns false if that was the last life; the actors then stay where they are.
+    pub fn lose_life(&mut self) -> bool {
+        let scoreboard = &mut self.scoreboard;
+        scoreboard.lives = scoreboard.lives.saturating_sub(1);
+        if scoreboard.lives == 0 {
+            return false;
+        }
+        self.reset_actors();
+        true
+    }
+
+    // Refills the maze and starts the next level.
+    pub fn next_level(&mut self) {
+        self.map = self.initial_map.clone();
+        self.dots_left = self.dot_count;
+        self.scoreboard.level += 1;
+        self.reset_actors();
+    }
+
+    // Runs one tick with the intent `controller` asks for.
+    pub fn step(&mut self, controller: &mut dyn Controller) {
+        let intent = controller.intent(self);
+        self.tick(intent);
+    }
+
+    // Advances the game by one tick. `intent` is the direction the player asks for;
+    // `Direction::Stopped` means no new request, so the last one stays in effect.
+    pub fn tick(&mut self, intent: Direction) {
+        if self.player_caught || self.is_level_cleared() {
+            return;
+        }
+        self.tick += 1;
+        self.events.clear();
+
+        if intent != Direction::Stopped {
+            self.player.desired_direction = intent;
+        }
+        self.move_player();
+        self.eat_dot();
+        self.update_fruit();
+        self.update_phase();
+        for i in 0..self.ghosts.len() {
+            self.update_ghost(i);
+        }
+        self.check_collisions();
+    }
+
+    // The player's collision rect after one tick of movement in `direction`, as tested
+    // with `is_rect_walkable` to decide whether the player may move or turn that way.
+    pub fn player_step_rect(&self, direction: Direction) -> Rect {
+        let step = PLAYER_SPEED * TICK_SECONDS;
+        collision_rect_at(self.player.pos + direction_vec(direction) * step)
+    }
+
+    fn move_player(&mut self) {
+        // Take the requested direction as soon as the player fits that way.
+        let desired = self.player.desired_direction;
+        if desired != Direction::Stopped
+            && is_rect_walkable(self.player_step_rect(desired), &self.map)
+        {
+            self.player.direction = desired;
+        }
+
+        let direction = self.player.direction;
+        let next_rect = self.player_step_rect(direction);
+        let player = &mut self.player;
+        if is_rect_walkable(next_rect, &self.map) {
+            player.pos += direction_vec(direction) * PLAYER_SPEED * TICK_SECONDS;
+        } else {
+            player.direction = Direction::Stopped;
+        }
+        if player.direction != Direction::Stopped {
+            player.facing = player.direction;
+        }
+    }
+
+    fn eat_dot(&mut self) {
+        let (x, y) = tile_at_pixel(self.player.pos.x, self.player.pos.y);
+        let points = match self.map.get(x, y) {
+            Some(2) => score::DOT_POINTS,
+            Some(3) => score::BIG_DOT_POINTS,
+            _ => return,
+        };
+        self.map.set(x, y, 0);
+        self.dots_left -= 1;
+        self.add_points(points);
+
+        if points == score::DOT_POINTS {
+            self.events.push(GameEvent::DotEaten { tile: (x, y) });
+        } else {
+            self.events.push(GameEvent::PelletEaten { tile: (x, y) });
+            let time_left = frightened_seconds(self.scoreboard.level);
+            self.ghost_combo = 0;
+            for ghost in &mut self.ghosts {
+                if ghost.mode == GhostMode::Eyes {
+                    continue;
+                }
+                if ghost.mode == GhostMode::Normal && !ghost.in_house {
+                    ghost.reverse();
+                }
+                ghost.mode = GhostMode::Frightened { time_left };
+            }
+        }
+
+        let dots_eaten = self.dot_count - self.dots_left;
+        if FRUIT_DOTS_EATEN.contains(&dots_eaten) {
+            let fruit = Fruit::for_level(self.scoreboard.level);
+            self.fruit = Some(FruitState {
+                fruit,
+                time_left: FRUIT_SECONDS,
+            });
+            let pos = tile_center(FRUIT_TILE);
+            self.events.push(GameEvent::FruitSpawned { fruit, pos });
+        }
+        if self.dots_left == 0 {
+            let level = self.scoreboard.level;
+            self.events.push(GameEvent::LevelCleared { level });
+        }
+    }
+
+    // Counts down the bonus fruit, or hands out its points if the player reached it.
+    fn update_fruit(&mut self) {
+        let Some(state) = &mut self.fruit else {
+            return;
+        };
+        state.time_left -= TICK_SECONDS;
+        let fruit = state.fruit;
+        let timed_out = state.time_left <= 0.0;
+        if tile_at_pixel(self.player.pos.x, self.player.pos.y) == FRUIT_TILE {
+            let points = fruit.points();
+            self.add_points(points);
+            let pos = tile_center(FRUIT_TILE);
+            self.events
+                .push(GameEvent::FruitEaten { fruit, pos, points });
+            self.fruit = None;
+        } else if timed_out {
+            self.fruit = None;
+        }
+    }
+
+    // Adds to the current player's score, reporting an extra life if it earned one.
+    fn add_points(&mut self, points: u32) {
+        if self.scoreboard.add_points(points) {
+            self.events.push(GameEvent::ExtraLife);
+        }
+    }
+
+    // Runs the scatter/chase schedule, which pauses while any ghost is frightened.
+    // Ghosts turn around whenever the phase changes.
+    fn update_phase(&mut self) {
+        let frightened = self
+            .ghosts
+            .iter()
+            .any(|g| matches!(g.mode, GhostMode::Frightened { .. }));
+        let Some(&length) = PHASE_SCHEDULE.get(self.phase_index) else {
+            return;
+        };
+        if frightened {
+            return;
+        }
+        self.phase_time += TICK_SECONDS;
+        if self.phase_time >= length {
+            self.phase_index += 1;
+            self.phase_time = 0.0;
+            for ghost in &mut self.ghosts {
+                if ghost.mode == GhostMode::Normal && !ghost.in_house {
+                    ghost.reverse();
+                }
+            }
+        }
+    }
+
+    fn update_ghost(&mut self, i: usize) {
+        self.ghosts[i].mode.update(TICK_SECONDS);
+
+        let ghost = &mut self.ghosts[i];
+        if ghost.in_house && ghost.release_time > 0.0 {
+            ghost.release_time -= TICK_SECONDS;
+            return;
+        }
+
+        self.ghosts[i].target = self.ghost_target(i);
+        let mut distance = self.ghosts[i].speed() * TICK_SECONDS;
+        while distance > 0.0 {
+            let ghost = &self.ghosts[i];
+            let tile = ghost.tile();
+            let center = tile_center(tile);
+            let to_center = (center - ghost.pos).dot(direction_vec(ghost.direction));
+            let stopped = ghost.direction == Direction::Stopped;
+
+            if (stopped || (0.0..=distance).contains(&to_center)) && ghost.decided_at != Some(tile)
+            {
+                let ghost = &mut self.ghosts[i];
+                ghost.pos = center;
+                ghost.decided_at = Some(tile);
+                if !stopped {
+                    distance -= to_center;
+                }
+                self.arrive_at_center(i, tile);
+                let direction = self.choose_direction(i, tile);
+                self.ghosts[i].direction = direction;
+                if direction == Direction::Stopped {
+                    break;
+                }
+                continue;
+            }
+            if stopped {
+                break;
+            }
+            let ghost = &mut self.ghosts[i];
+            ghost.pos += direction_vec(ghost.direction) * distance;
+            distance = 0.0;
+        }
+    }
+
+    // Handles reaching the center of a ghost house tile: leaving through the door, or
+    // eyes coming home and turning back into a ghost.
+    fn arrive_at_center(&mut self, i: usize, tile: (isize, isize)) {
+        let ghost = &mut self.ghosts[i];
+        if ghost.mode == GhostMode::Eyes && tile == HOUSE_CENTER {
+            ghost.mode = GhostMode::Normal;
+            ghost.in_house = true;
+            ghost.release_time = 0.0;
+        } else if ghost.in_house && ghost.mode != GhostMode::Eyes && tile == HOUSE_DOOR {
+            ghost.in_house = false;
+            ghost.direction = Direction::West;
+        }
+        let target = self.ghost_target(i);
+        self.ghosts[i].target = target;
+    }
+
+    // The tile a ghost is steering towards, following the arcade personalities.
+    fn ghost_target(&self, i: usize) -> (isize, isize) {
+        let ghost = &self.ghosts[i];
+        if ghost.mode == GhostMode::Eyes {
+            return HOUSE_CENTER;
+        }
+        if ghost.in_house {
+            return HOUSE_DOOR;
+        }
+        if let GhostMode::Frightened { .. } = ghost.mode {
+            return ghost.tile();
+        }
+        if self.phase() == GhostPhase::Scatter {
+            return ghost.scatter_target;
+        }
+
+        let player = tile_at_pixel(self.player.pos.x, self.player.pos.y);
+        let ahead = |tiles: isize| {
+            let (dx, dy) = self.player.facing.delta();
+            (player.0 + dx * tiles, player.1 + dy * tiles)
+        };
+        match ghost.name {
+            // Aims a few tiles ahead of the player.
+            "pinky" => ahead(4),
+            // Doubles the vector from Blinky to the tile two ahead of the player.
+            "inky" => {
+                let pivot = ahead(2);
+                let blinky = self
+                    .ghosts
+                    .iter()
+                    .find(|g| g.name == "blinky")
+                    .map_or(pivot, |g| g.tile());
+                (2 * pivot.0 - blinky.0, 2 * pivot.1 - blinky.1)
+            }
+            // Chases from afar but retreats to its corner when closer than eight tiles.
+            "clyde" => {
+                let (dx, dy) = (player.0 - ghost.tile().0, player.1 - ghost.tile().1);
+                if dx * dx + dy * dy > 64 {
+                    player
+                } else {
+                    ghost.scatter_target
+                }
+            }
+            // Blinky, and any other ghost, goes straight for the player.
+            _ => player,
+        }
+    }
+
+    // Picks the heading at a tile center: never back the way it came (except in the house
+    // or at a dead end), and otherwise the free neighbor closest to the target, with ties
+    // going north, west, south, east. Frightened ghosts pick a free neighbor at random.
+    fn choose_direction(&mut self, i: usize, tile: (isize, isize)) -> Direction {
+        let ghost = &self.ghosts[i];
+        let options = self.ghost_options(i, tile, ghost.direction);
+        if let GhostMode::Frightened { .. } = ghost.mode
+            && !ghost.in_house
+            && options.len() > 1
+        {
+            let pick = self.rng.below(options.len() as u32) as usize;
+            return options[pick];
+        }
+        closest_to_target(&options, tile, ghost.target)
+    }
+
+    // The headings a ghost may leave `tile` in when arriving with `heading`, in tie-break
+    // order. Reversing is only allowed in the house, or when there is no other way.
+    fn ghost_options(&self, i: usize, tile: (isize, isize), heading: Direction) -> Vec<Direction> {
+        let ghost = &self.ghosts[i];
+        let may_use_door = ghost.in_house || ghost.mode == GhostMode::Eyes;
+        let reverse = heading.opposite();
+        let free = |direction: Direction| {
+            let (dx, dy) = direction.delta();
+            ghost_can_enter(&self.map, tile.0 + dx, tile.1 + dy, may_use_door)
+        };
+        let options: Vec<Direction> = [
+            Direction::North,
+            Direction::West,
+            Direction::South,
+            Direction::East,
+        ]
+        .into_iter()
+        .filter(|&d| free(d) && (d != reverse || ghost.in_house))
+        .collect();
+        if options.is_empty() && free(reverse) {
+            return vec![reverse];
+        }
+        options
+    }
+
+    // The tiles a ghost will pass on its way to its current target if nothing changes,
+    // at most `max_tiles` of them. Empty for frightened ghosts, whose turns are random.
+    pub fn ghost_path(&self, i: usize, max_tiles: usize) -> Vec<(isize, isize)> {
+        let ghost = &self.ghosts[i];
+        if let GhostMode::Frightened { .. } = ghost.mode
+            && !ghost.in_house
+        {
+            return Vec::new();
+        }
+        let mut tile = ghost.tile();
+        let mut heading = ghost.direction;
+        let mut path = Vec::new();
+        // A ghost past its decision point keeps going to the next tile first.
+        if ghost.decided_at == Some(tile) && heading != Direction::Stopped {
+            let (dx, dy) = heading.delta();
+            tile = (tile.0 + dx, tile.1 + dy);
+            path.push(tile);
+        }
+        while path.len() < max_tiles && tile != ghost.target {
+            let options = self.ghost_options(i, tile, heading);
+            heading = closest_to_target(&options, tile, ghost.target);
+            if heading == Direction::Stopped {
+                break;
+            }
+            let (dx, dy) = heading.delta();
+            tile = (tile.0 + dx, tile.1 + dy);
+            path.push(tile);
+        }
+        path
+    }
+
+    // Ghosts and the player meet when they share a tile.
+    fn check_collisions(&mut self) {
+        let player_tile = tile_at_pixel(self.player.pos.x, self.player.pos.y);
+        for i in 0..self.ghosts.len() {
+            if self.ghosts[i].tile() != player_tile {
+                continue;
+            }
+            match self.ghosts[i].mode {
+                GhostMode::Normal => {
+                    self.player_caught = true;
+                    let pos = self.player.pos;
+                    self.events.push(GameEvent::PlayerCaught { pos });
+                    return;
+                }
+                GhostMode::Frightened { .. } => {
+                    self.ghosts[i].mode = GhostMode::Eyes;
+                    let points = GHOST_POINTS << self.ghost_combo.min(3);
+                    self.ghost_combo += 1;
+                    self.add_points(points);
+                    let ghost = &self.ghosts[i];
+                    self.events.push(GameEvent::GhostEaten {
+                        ghost: ghost.name,
+                        pos: ghost.pos,
+                        points,
+                    });
+                }
+                GhostMode::Eyes => {}
+            }
+        }
+    }
+}
+
+// The option whose next tile is closest to `target`; the first one wins ties.
+fn closest_to_target(
+    options: &[Direction],
+    tile: (isize, isize),
+    target: (isize, isize),
+) -> Direction {
+    options
+        .iter()
+        .copied()
+        .min_by_key(|d| {
+            let (dx, dy) = d.delta();
+            let (x, y) = (tile.0 + dx - target.0, tile.1 + dy - target.1);
+            x * x + y * y
+        })
+        .unwrap_or(Direction::Stopped)
+}
+
+// Whether a ghost may step onto a tile. The door and the house behind it only let ghosts
+// leaving the house and returning eyes through.
+fn ghost_can_enter(map: &Grid<u8>, x: isize, y: isize, may_use_door: bool) -> bool {
+    let ((left, top), (right, bottom)) = HOUSE_AREA;
+    let in_house = (left..=right).contains(&x) && (top..=bottom).contains(&y);
+    match map.get(x, y) {
+        None | Some(1) => false,
+        Some(4) => may_use_door,
+        Some(_) => may_use_door || !in_house,
+    }
+}
+
+// Screen position of a tile's center.
+pub fn tile_center((x, y): (isize, isize)) -> Vec2 {
+    Vec2::new(
+        (x as f32 + 0.5) * TILE_SIZE,
+        (y as f32 + 0.5) * TILE_SIZE + MAZE_OFFSET_Y,
+    )
+}
+
+fn direction_vec(direction: Direction) -> Vec2 {
+    let (dx, dy) = direction.delta();
+    Vec2::new(dx as f32, dy as f32)
+}
+
+fn count_dots(map: &Grid<u8>) -> usize {
+    map.iter()
+        .filter(|&(_, _, &tile)| tile == 2 || tile == 3)
+        .count()
+}
+
+// How long power pellets frighten the ghosts, shrinking with every level.
+fn frightened_seconds(level: u32) -> f32 {
+    (7.0 - level as f32).max(1.0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::load_level_from_string;
+
+    const LEVEL: &str = include_str!("../resources/levels/level1.txt");
+
+    fn level_one() -> Simulation {
+        Simulation::new(load_level_from_string(LEVEL), 1)
+    }
+
+    // Level one with only the player in it.
+    fn without_ghosts() -> Simulation {
+        let mut sim = level_one();
+        sim.ghosts.clear();
+        sim
+    }
+
+    fn run(sim: &mut Simulation, intent: Direction, ticks: usize) {
+        for _ in 0..ticks {
+            sim.tick(intent);
+        }
+    }
+
+    fn player_tile(sim: &Simulation) -> (isize, isize) {
+        tile_at_pixel(sim.player.pos.x, sim.player.pos.y)
+    }
+
+    #[test]
+    fn player_moves_at_a_fixed_speed_and_stops_at_walls() {
+        let mut sim = without_ghosts();
+        let start = sim.player.pos;
+        sim.tick(Direction::East);
+        assert_eq!(sim.player.direction, Direction::East);
+        let moved = sim.player.pos - start;
+        assert!((moved.x - PLAYER_SPEED * TICK_SECONDS).abs() < 1e-4);
+        assert_eq!(moved.y, 0.0);
+
+        // The corridor ends in a wall at tile 22.
+        run(&mut sim, Direction::Stopped, 200);
+        assert_eq!(sim.player.direction, Direction::Stopped);
+        assert_eq!(sim.player.facing, Direction::East);
+        assert_eq!(player_tile(&sim), (21, 23));
+        assert!((sim.player.pos.x - tile_center((21, 23)).x).abs() < 1.0);
+    }
+
+    #[test]
+    fn requested_turn_waits_until_the_way_is_free() {
+        let mut sim = without_ghosts();
+        sim.tick(Direction::West);
+        // There is a wall above the start tile, so the player keeps going west.
+        sim.tick(Direction::North);
+        assert_eq!(sim.player.direction, Direction::West);
+        assert_eq!(sim.player.desired_direction, Direction::North);
+
+        let mut ticks = 0;
+        while sim.player.direction == Direction::West {
+            sim.tick(Direction::Stopped);
+            ticks += 1;
+            assert!(ticks < 20, "never turned north");
+        }
+        assert_eq!(sim.player.direction, Direction::North);
+        assert!((sim.player.pos.x - tile_center((12, 23)).x).abs() < 1.0);
+        run(&mut sim, Direction::Stopped, 15);
+        assert_eq!(player_tile(&sim), (12, 22));
+    }
+
+    #[test]
+    fn eating_a_dot_scores_and_clears_the_tile() {
+        let mut sim = without_ghosts();
+        let dots = sim.dots_left();
+        while sim.dots_left() == dots {
+            sim.tick(Direction::West);
+        }
+        assert_eq!(sim.map.get(12, 23), Some(&0));
+        assert_eq!(sim.dots_left(), dots - 1);
+        assert_eq!(sim.scoreboard.score(), score::DOT_POINTS);
+        let events: Vec<_> = sim.drain_events().collect();
+        assert_eq!(events, [GameEvent::DotEaten { tile: (12, 23) }]);
+    }
+
+    #[test]
+    fn energizer_frightens_and_reverses_the_ghosts() {
+        let mut sim = level_one();
+        sim.map.set(12, 23, 3);
+        let blinky_heading = sim.ghosts[0].direction;
+        while sim.scoreboard.score() == 0 {
+            sim.tick(Direction::West);
+        }
+        assert_eq!(sim.scoreboard.score(), score::BIG_DOT_POINTS);
+        let events: Vec<_> = sim.drain_events().collect();
+        assert_eq!(events, [GameEvent::PelletEaten { tile: (12, 23) }]);
+        for ghost in &sim.ghosts {
+            assert!(
+                matches!(ghost.mode, GhostMode::Frightened { .. }),
+                "{}",
+                ghost.name
+            );
+        }
+        // Blinky is outside the house and turns around; the others wait inside.
+        assert_ne!(sim.ghosts[0].direction, blinky_heading);
+    }
+
+    #[test]
+    fn frightened_mode_runs_out_and_pauses_the_phase_schedule() {
+        let mut sim = level_one();
+        for ghost in &mut sim.ghosts {
+            ghost.mode = GhostMode::Frightened { time_left: 1.0 };
+        }
+        run(&mut sim, Direction::Stopped, 59);
+        assert!(matches!(sim.ghosts[0].mode, GhostMode::Frightened { .. }));
+        assert_eq!(sim.phase_time_left(), Some(PHASE_SCHEDULE[0]));
+
+        run(&mut sim, Direction::Stopped, 2);
+        assert!(sim.ghosts.iter().all(|g| g.mode == GhostMode::Normal));
+        sim.tick(Direction::Stopped);
+        assert!(sim.phase_time_left().unwrap() < PHASE_SCHEDULE[0]);
+    }
+
+    #[test]
+    fn frightened_time_shrinks_with_the_level() {
+        assert_eq!(frightened_seconds(1), 6.0);
+        assert_eq!(frightened_seconds(2), 5.0);
+        assert_eq!(frightened_seconds(6), 1.0);
+        assert_eq!(frightened_seconds(20), 1.0);
+    }
+
+    #[test]
+    fn eating_ghosts_doubles_the_points_up_to_1600() {
+        let mut sim = level_one();
+        let pos = sim.player.pos;
+        for ghost in &mut sim.ghosts {
+            ghost.pos = pos;
+            ghost.mode = GhostMode::Frightened { time_left: 5.0 };
+        }
+        sim.tick(Direction::Stopped);
+
+        let points: Vec<u32> = sim
+            .drain_events()
+            .filter_map(|event| match event {
+                GameEvent::GhostEaten { points, .. } => Some(points),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(points, [200, 400, 800, 1600]);
+        assert_eq!(sim.scoreboard.score(), 3000);
+        assert!(sim.ghosts.iter().all(|g| g.mode == GhostMode::Eyes));
+        assert!(!sim.is_player_caught());
+
+        // A fifth ghost on the same pellet stays at 1600.
+        sim.ghosts[0].pos = pos;
+        sim.ghosts[0].mode = GhostMode::Frightened { time_left: 5.0 };
+        sim.tick(Direction::Stopped);
+        assert_eq!(sim.scoreboard.score(), 4600);
+    }
+
+    #[test]
+    fn phases_alternate_along_the_schedule_then_chase_for_good() {
+        let mut sim = without_ghosts();
+        let mut phases = vec![sim.phase()];
+        let seconds: f32 = PHASE_SCHEDULE.iter().sum();
+        for _ in 0..(seconds as usize + 1) * TICK_RATE as usize {
+            sim.tick(Direction::Stopped);
+            if phases.last() != Some(&sim.phase()) {
+                phases.push(sim.phase());
+            }
+        }
+        use GhostPhase::{Chase, Scatter};
+        assert_eq!(
+            phases,
+            [
+                Scatter, Chase, Scatter, Chase, Scatter, Chase, Scatter, Chase
+            ]
+        );
+        assert_eq!(sim.phase_time_left(), None);
+    }
+
+    #[test]
+    fn ghosts_outside_the_house_turn_around_when_the_phase_changes() {
+        let mut sim = level_one();
+        let ticks = (PHASE_SCHEDULE[0] * TICK_RATE as f32) as usize - 1;
+        run(&mut sim, Direction::Stopped, ticks);
+        assert_eq!(sim.phase(), GhostPhase::Scatter);
+        let blinky = &sim.ghosts[0];
+        assert!(!blinky.in_house);
+        let heading = blinky.direction;
+
+        while sim.phase() == GhostPhase::Scatter {
+            sim.tick(Direction::Stopped);
+        }
+        assert_ne!(sim.ghosts[0].direction, heading);
+    }
+
+    #[test]
+    fn caught_player_stops_the_simulation() {
+        let mut sim = level_one();
+        sim.ghosts[0].pos = sim.player.pos;
+        sim.tick(Direction::Stopped);
+        assert!(sim.is_player_caught());
+        let events: Vec<_> = sim.drain_events().collect();
+        assert_eq!(
+            events,
+            [GameEvent::PlayerCaught {
+                pos: sim.player.pos
+            }]
+        );
+
+        let tick = sim.tick_count();
+        sim.tick(Direction::West);
+        assert_eq!(sim.tick_count(), tick);
+    }
+
+    #[test]
+    fn losing_a_life_resets_the_actors_until_none_are_left() {
+        let mut sim = level_one();
+        let start = level_one();
+        sim.scoreboard.lives = 2;
+        // Eat the dots up to the east wall, where the player comes to a stop.
+        run(&mut sim, Direction::East, 150);
+        assert_eq!(sim.player.direction, Direction::Stopped);
+        sim.ghosts[0].pos = sim.player.pos;
+        sim.tick(Direction::Stopped);
+        assert!(sim.is_player_caught());
+
+        assert!(sim.lose_life());
+        assert_eq!(sim.scoreboard.lives, 1);
+        assert!(!sim.is_player_caught());
+        assert_eq!(sim.player, start.player);
+        assert_eq!(sim.ghosts, start.ghosts);
+        assert_eq!(sim.phase_time_left(), Some(PHASE_SCHEDULE[0]));
+        // Eaten dots stay eaten.
+        assert!(sim.dots_left() < start.dots_left());
+
+        sim.ghosts[0].pos = sim.player.pos;
+        sim.tick(Direction::Stopped);
+        assert!(!sim.lose_life());
+        assert_eq!(sim.scoreboard.lives, 0);
+        assert!(sim.is_player_caught());
+    }
+
+    #[test]
+    fn next_level_refills_the_maze() {
+        let mut sim = without_ghosts();
+        let full = sim.dots_left();
+        run(&mut sim, Direction::West, 60);
+        let score = sim.scoreboard.score();
+        assert!(sim.dots_left() < full);
+
+        sim.next_level();
+        assert_eq!(sim.scoreboard.level, 2);
+        assert_eq!(sim.dots_left(), full);
+        assert_eq!(sim.map, level_one().map);
+        assert_eq!(sim.scoreboard.score(), score);
+        assert_eq!(sim.player, PlayerState::at_start());
+        assert_eq!(sim.ghosts.len(), GHOST_STARTS.len());
+    }
+
+    #[test]
+    fn clearing_the_last_dot_ends_the_level() {
+        let mut sim = without_ghosts();
+        for (x, y) in sim
+            .initial_map
+            .iter()
+            .map(|(x, y, _)| (x, y))
+            .collect::<Vec<_>>()
+        {
+            if matches!(sim.map.get(x as isize, y as isize), Some(2 | 3)) && (x, y) != (12, 23) {
+                sim.map.set(x as isize, y as isize, 0);
+            }
+        }
+        sim.dots_left = 1;
+        while !sim.is_level_cleared() {
+            sim.tick(Direction::West);
+        }
+        let events: Vec<_> = sim.drain_events().collect();
+        assert!(events.contains(&GameEvent::LevelCleared { level: 1 }));
+        let tick = sim.tick_count();
+        sim.tick(Direction::West);
+        assert_eq!(sim.tick_count(), tick);
+    }
+}
@@ -0,0 +1,356 @@
+use crate::{Direction, Grid};
+use ggez::glam::Vec2;
+
+// A 64-bit FNV-1a hasher. Unlike std's `DefaultHasher` its output is specified, so hashes
+// can be stored in files and compared between machines, builds and program versions.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct StateHasher {
+    hash: u64,
+}
+
+impl Default for StateHasher {
+    fn default() -> StateHasher {
+        StateHasher::new()
+    }
+}
+
+impl StateHasher {
+    pub fn new() -> StateHasher {
+        StateHasher {
+            hash: 0xcbf2_9ce4_8422_2325,
+        }
+    }
+
+    pub fn write(&mut self, bytes: &[u8]) {
+        for &byte in bytes {
+            self.hash ^= byte as u64;
+            self.hash = self.hash.wrapping_mul(0x0000_0100_0000_01b3);
+        }
+    }
+
+    pub fn finish(&self) -> u64 {
+        self.hash
+    }
+}
+
+// Simulation state that can be fed to a `StateHasher`. Numbers are hashed little-endian
+// and floats by their bits, so equal states hash equally everywhere and the smallest
+// drift in a position changes the hash.
+pub trait HashState {
+    fn hash_state(&self, hasher: &mut StateHasher);
+}
+
+// The hash of a single value.
+pub fn hash_of(value: &(impl HashState + ?Sized)) -> u64 {
+    let mut hasher = StateHasher::new();
+    value.hash_state(&mut hasher);
+    hasher.finish()
+}
+
+macro_rules! hash_state_le_bytes {
+    ($($t:ty),*) => {
+        $(impl HashState for $t {
+            fn hash_state(&self, hasher: &mut StateHasher) {
+                hasher.write(&self.to_le_bytes());
+            }
+        })*
+    };
+}
+
+hash_state_le_bytes!(u8, u32, u64);
+
+impl HashState for usize {
+    fn hash_state(&self, hasher: &mut StateHasher) {
+        (*self as u64).hash_state(hasher);
+    }
+}
+
+impl HashState for isize {
+    fn hash_state(&self, hasher: &mut StateHasher) {
+        (*self as i64 as u64).hash_state(hasher);
+    }
+}
+
+impl HashState for bool {
+    fn hash_state(&self, hasher: &mut StateHasher) {
+        (*self as u8).hash_state(hasher);
+    }
+}
+
+impl HashState for f32 {
+    fn hash_state(&self, hasher: &mut StateHasher) {
+        self.to_bits().hash_state(hasher);
+    }
+}
+
+impl HashState for str {
+    fn hash_state(&self, hasher: &mut StateHasher) {
+        self.len().hash_state(hasher);
+        hasher.write(self.as_bytes());
+    }
+}
+
+impl HashState for Vec2 {
+    fn hash_state(&self, hasher: &mut StateHasher) {
+        self.x.hash_state(hasher);
+        self.y.hash_state(hasher);
+    }
+}
+
+impl HashState for Direction {
+    fn hash_state(&self, hasher: &mut StateHasher) {
+        (*self as u8).hash_state(hasher);
+    }
+}
+
+impl<A: HashState, B: HashState> HashState for (A, B) {
+    fn hash_state(&self, hasher: &mut StateHasher) {
+        self.0.hash_state(hasher);
+        self.1.hash_state(hasher);
+    }
+}
+
+impl<T: HashState> HashState for Option<T> {
+    fn hash_state(&self, hasher: &mut StateHasher) {
+        match self {
+            Some(value) => {
+                true.hash_state(hasher);
+                value.hash_state(hasher);
+            }
+            None => false.hash_state(hasher),
+        }
+    }
+}
+
+impl<T: HashState> HashState for [T] {
+    fn hash_state(&self, hasher: &mut StateHasher) {
+        self.len().hash_state(hasher);
+        for value in self {
+            value.hash_state(hasher);
+        }
+    }
+}
+
+impl<T: HashState> HashState for Grid<T> {
+    fn hash_state(&self, hasher: &mut StateHasher) {
+        self.width().hash_state(hasher);
+        self.height().hash_state(hasher);
+        self.cells().hash_state(hasher);
+    }
+}
+
+// A simulation state as named fields ("player.pos", "ghosts.inky.mode", "rng", ...), each
+// hashed on its own, so two states that differ can say where.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct StateFields {
+    fields: Vec<(String, u64)>, // In a fixed order, the same for every state.
+}
+
+impl StateFields {
+    pub fn new() -> StateFields {
+        StateFields::default()
+    }
+
+    pub fn add(&mut self, name: impl Into<String>, value: &(impl HashState + ?Sized)) {
+        self.fields.push((name.into(), hash_of(value)));
+    }
+
+    pub fn fields(&self) -> &[(String, u64)] {
+        &self.fields
+    }
+
+    // One hash for the whole state.
+    pub fn hash(&self) -> u64 {
+        let mut hasher = StateHasher::new();
+        for (name, hash) in &self.fields {
+            name.as_str().hash_state(&mut hasher);
+            hash.hash_state(&mut hasher);
+        }
+        hasher.finish()
+    }
+
+    // The first field whose hash differs from `other`'s, or None if the states match.
+    pub fn first_difference<'a>(&'a self, other: &'a StateFields) -> Option<&'a str> {
+        for (i, (name, hash)) in self.fields.iter().enumerate() {
+            if !matches!(other.fields.get(i), Some((n, h)) if n == name && h == hash) {
+                return Some(name);
+            }
+        }
+        // `other` has fields this state lacks, e.g. an extra ghost.
+        other
+            .fields
+            .get(self.fields.len())
+            .map(|(name, _)| name.as_str())
+    }
+}
+
+// Where two runs first went apart: the first tick count at which their states differ, and
+// the first field that differs there. `field` is "end of run" if one run stopped earlier.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Divergence {
+    pub tick: u64,
+    pub field: String,
+}
+
+// The per-tick states of one run, e.g. a replay played back on two machines or before and
+// after a change to the rules.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct RunTrace {
+    states: Vec<(u64, StateFields)>, // By tick count, in the order recorded.
+}
+
+impl RunTrace {
+    pub fn new() -> RunTrace {
+        RunTrace::default()
+    }
+
+    pub fn record(&mut self, tick: u64, state: StateFields) {
+        self.states.push((tick, state));
+    }
+
+    pub fn len(&self) -> usize {
+        self.states.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.states.is_empty()
+    }
+
+    // The combined hash of every recorded state, in order.
+    pub fn hashes(&self) -> impl Iterator<Item = (u64, u64)> + '_ {
+        self.states
+            .iter()
+            .map(|(tick, state)| (*tick, state.hash()))
+    }
+
+    // Compares two runs state by state. Returns None if they match all the way.
+    pub fn first_divergence(&self, other: &RunTrace) -> Option<Divergence> {
+        for (i, (tick, state)) in self.states.iter().enumerate() {
+            let Some((other_tick, other_state)) = other.states.get(i) else {
+                return Some(Divergence {
+                    tick: *tick,
+                    field: "end of run".to_string(),
+                });
+            };
+            if other_tick != tick {
+                return Some(Divergence {
+                    tick: (*tick).min(*other_tick),
+                    field: "tick".to_string(),
+                });
+            }
+            if let Some(field) = state.first_difference(other_state) {
+                return Some(Divergence {
+                    tick: *tick,
+                    field: field.to_string(),
+                });
+            }
+        }
+        other
+            .states
+            .get(self.states.len())
+            .map(|(tick, _)| Divergence {
+                tick: *tick,
+                field: "end of run".to_string(),
+            })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn state(pos: f32, lives: u32) -> StateFields {
+        let mut fields = StateFields::new();
+        fields.add("player.pos", &Vec2::new(pos, 0.0));
+        fields.add("player.direction", &Direction::West);
+        fields.add("lives", &lives);
+        fields
+    }
+
+    // A run of `ticks` ticks, the player moving one pixel a tick, that loses a life at
+    // `death_tick` if given.
+    fn trace(ticks: u64, death_tick: Option<u64>) -> RunTrace {
+        let mut trace = RunTrace::new();
+        for tick in 1..=ticks {
+            let lives = if death_tick.is_some_and(|death| tick >= death) {
+                2
+            } else {
+                3
+            };
+            trace.record(tick, state(tick as f32, lives));
+        }
+        trace
+    }
+
+    #[test]
+    fn fnv1a_matches_the_reference_values() {
+        assert_eq!(StateHasher::new().finish(), 0xcbf2_9ce4_8422_2325);
+        let mut hasher = StateHasher::new();
+        hasher.write(b"a");
+        assert_eq!(hasher.finish(), 0xaf63_dc4c_8601_ec8c);
+    }
+
+    #[test]
+    fn identical_states_have_no_difference() {
+        assert_eq!(state(1.0, 3).first_difference(&state(1.0, 3)), None);
+        assert_eq!(state(1.0, 3).hash(), state(1.0, 3).hash());
+    }
+
+    #[test]
+    fn first_difference_names_the_first_differing_field() {
+        assert_eq!(
+            state(1.0, 3).first_difference(&state(1.0, 2)),
+            Some("lives")
+        );
+        assert_eq!(
+            state(1.0, 3).first_difference(&state(1.5, 2)),
+            Some("player.pos")
+        );
+        assert_ne!(state(1.0, 3).hash(), state(1.0, 2).hash());
+    }
+
+    #[test]
+    fn extra_fields_count_as_a_difference() {
+        let mut longer = state(1.0, 3);
+        longer.add("ghosts.count", &4usize);
+        assert_eq!(
+            state(1.0, 3).first_difference(&longer),
+            Some("ghosts.count")
+        );
+        assert_eq!(
+            longer.first_difference(&state(1.0, 3)),
+            Some("ghosts.count")
+        );
+    }
+
+    #[test]
+    fn identical_runs_do_not_diverge() {
+        assert_eq!(
+            trace(100, Some(40)).first_divergence(&trace(100, Some(40))),
+            None
+        );
+        assert_eq!(RunTrace::new().first_divergence(&RunTrace::new()), None);
+    }
+
+    #[test]
+    fn divergence_reports_the_first_tick_and_field() {
+        let divergence = trace(100, Some(40)).first_divergence(&trace(100, Some(60)));
+        assert_eq!(
+            divergence,
+            Some(Divergence {
+                tick: 40,
+                field: "lives".to_string(),
+            })
+        );
+    }
+
+    #[test]
+    fn a_run_ending_early_diverges_at_its_end() {
+        let expected = Some(Divergence {
+            tick: 51,
+            field: "end of run".to_string(),
+        });
+        assert_eq!(trace(50, None).first_divergence(&trace(80, None)), expected);
+        assert_eq!(trace(80, None).first_divergence(&trace(50, None)), expected);
+    }
+}
@@ -0,0 +1,470 @@
+use crate::state_hash::StateHasher;
+use serde::Deserialize;
+use std::collections::{BTreeMap, HashMap};
+
+// A small synthesizer in the style of the Namco WSG sound chip of the arcade: each voice
+// plays a 32-step, 4-bit wavetable at a given pitch. Sounds are described as notes and
+// pitch sweeps per voice and rendered to 16-bit mono PCM. Nothing here touches the
+// filesystem or an audio device, so the same description always renders the same samples.
+
+pub const SAMPLE_RATE: u32 = 22050;
+pub const WAVETABLE_LEN: usize = 32;
+
+// One cycle of a waveform as 4-bit steps, 0 to 15.
+pub type Wavetable = [u8; WAVETABLE_LEN];
+
+// Overall level of the mix, leaving headroom for several voices at full volume.
+const MASTER_VOLUME: f32 = 0.5;
+
+// A stretch of one voice: a tone gliding from `from_hz` to `to_hz`, or silence at 0 Hz.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Segment {
+    pub from_hz: f32,
+    pub to_hz: f32,
+    pub seconds: f32,
+}
+
+impl Segment {
+    pub fn tone(hz: f32, seconds: f32) -> Segment {
+        Segment::sweep(hz, hz, seconds)
+    }
+
+    pub fn sweep(from_hz: f32, to_hz: f32, seconds: f32) -> Segment {
+        Segment {
+            from_hz,
+            to_hz,
+            seconds,
+        }
+    }
+
+    pub fn rest(seconds: f32) -> Segment {
+        Segment::tone(0.0, seconds)
+    }
+}
+
+// One channel of a sound: a waveform and what it plays.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Voice {
+    pub wave: Wavetable,
+    pub volume: f32, // 0.0 to 1.0.
+    pub gap: f32,    // Seconds of silence at the end of every tone, so repeated notes separate.
+    pub segments: Vec<Segment>,
+}
+
+impl Voice {
+    pub fn seconds(&self) -> f32 {
+        self.segments.iter().map(|s| s.seconds).sum()
+    }
+}
+
+// Frequency of a note name such as "A4" (440 Hz), "F#5" or "Bb2".
+pub fn note_hz(name: &str) -> Option<f32> {
+    let mut chars = name.chars();
+    let mut semitone: i32 = match chars.next()? {
+        'C' => 0,
+        'D' => 2,
+        'E' => 4,
+        'F' => 5,
+        'G' => 7,
+        'A' => 9,
+        'B' => 11,
+        _ => return None,
+    };
+    let rest = chars.as_str();
+    let octave = if let Some(octave) = rest.strip_prefix('#') {
+        semitone += 1;
+        octave
+    } else if let Some(octave) = rest.strip_prefix('b') {
+        semitone -= 1;
+        octave
+    } else {
+        rest
+    };
+    let octave: i32 = octave.parse().ok()?;
+    let midi = 12 * (octave + 1) + semitone;
+    Some(440.0 * 2f32.powf((midi - 69) as f32 / 12.0))
+}
+
+// Mixes the voices into samples at `SAMPLE_RATE`, as long as the longest voice.
+pub fn render(voices: &[Voice]) -> Vec<i16> {
+    let seconds = voices.iter().map(Voice::seconds).fold(0.0, f32::max);
+    let mut mix = vec![0.0f32; (seconds * SAMPLE_RATE as f32).round() as usize];
+    for voice in voices {
+        render_voice(voice, &mut mix);
+    }
+    mix.iter()
+        .map(|&s| ((s * MASTER_VOLUME).clamp(-1.0, 1.0) * i16::MAX as f32) as i16)
+        .collect()
+}
+
+// Adds one voice to `mix`. The wave's phase carries on from segment to segment, so glides
+// and note changes don't click.
+fn render_voice(voice: &Voice, mix: &mut [f32]) {
+    let rate = SAMPLE_RATE as f32;
+    let mut phase = 0.0f32; // Position in the wavetable.
+    let mut time = 0.0f32;
+    for segment in &voice.segments {
+        // Sample positions come from the running time rather than summed lengths, so
+        // rounding doesn't add up over long tunes.
+        let start = (time * rate).round() as usize;
+        time += segment.seconds;
+        let end = ((time * rate).round() as usize).min(mix.len());
+        let gap_start = end.saturating_sub((voice.gap * rate).round() as usize);
+        let length = end.saturating_sub(start).max(1) as f32;
+        for (i, sample) in mix.iter_mut().enumerate().take(end).skip(start) {
+            let t = (i - start) as f32 / length;
+            let hz = segment.from_hz + (segment.to_hz - segment.from_hz) * t;
+            if hz <= 0.0 {
+                continue;
+            }
+            phase = (phase + hz * WAVETABLE_LEN as f32 / rate) % WAVETABLE_LEN as f32;
+            if i >= gap_start {
+                continue;
+            }
+            let step = voice.wave[phase as usize] as f32;
+            *sample += (step - 7.5) / 7.5 * voice.volume;
+        }
+    }
+}
+
+// Wraps samples from `render` in a WAV file, for playback or listening to them elsewhere.
+pub fn encode_wav(samples: &[i16]) -> Vec<u8> {
+    let data_len = samples.len() as u32 * 2;
+    let mut wav = Vec::with_capacity(44 + data_len as usize);
+    wav.extend_from_slice(b"RIFF");
+    wav.extend_from_slice(&(36 + data_len).to_le_bytes());
+    wav.extend_from_slice(b"WAVEfmt ");
+    wav.extend_from_slice(&16u32.to_le_bytes()); // Format chunk size.
+    wav.extend_from_slice(&1u16.to_le_bytes()); // Integer PCM.
+    wav.extend_from_slice(&1u16.to_le_bytes()); // Mono.
+    wav.extend_from_slice(&SAMPLE_RATE.to_le_bytes());
+    wav.extend_from_slice(&(SAMPLE_RATE * 2).to_le_bytes()); // Bytes per second.
+    wav.extend_from_slice(&2u16.to_le_bytes()); // Bytes per sample.
+    wav.extend_from_slice(&16u16.to_le_bytes()); // Bits per sample.
+    wav.extend_from_slice(b"data");
+    wav.extend_from_slice(&data_len.to_le_bytes());
+    for sample in samples {
+        wav.extend_from_slice(&sample.to_le_bytes());
+    }
+    wav
+}
+
+// A stable hash of rendered samples, for checking that a sound still renders the same.
+pub fn samples_hash(samples: &[i16]) -> u64 {
+    let mut hasher = StateHasher::new();
+    for sample in samples {
+        hasher.write(&sample.to_le_bytes());
+    }
+    hasher.finish()
+}
+
+// Every sound of the game described for the synthesizer, e.g. `resources/sounds.json`:
+//
+//     {
+//       "waves": { "square": [15, 15, ..., 0, 0] },
+//       "sounds": {
+//         "fruit": [{ "wave": "square", "notes": [[500, 1400, 0.25]] }],
+//         "jingle": [
+//           { "wave": "square", "volume": 0.6, "beat": 0.13, "gap": 0.02,
+//             "notes": [["B4", 1], ["-", 0.5], ["F#5", 2]] }
+//         ]
+//       }
+//     }
+//
+// `waves` are 32 steps of 0 to 15 each. Every sound lists its voices. A voice's notes are
+// `[from_hz, to_hz, length]` glides, `[hz, length]` tones, `[name, length]` notes or
+// `["-", length]` rests, with lengths in beats of `beat` seconds (default 1). `volume`
+// defaults to 1, `gap` to 0 seconds, and `repeat` plays the notes that many times.
+#[derive(Debug, Clone, Deserialize)]
+struct SoundBankDef {
+    waves: HashMap<String, Vec<u8>>,
+    sounds: BTreeMap<String, Vec<VoiceDef>>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct VoiceDef {
+    wave: String,
+    #[serde(default = "default_one")]
+    volume: f32,
+    #[serde(default = "default_one")]
+    beat: f32,
+    #[serde(default)]
+    gap: f32,
+    #[serde(default = "default_repeat")]
+    repeat: u32,
+    notes: Vec<NoteDef>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(untagged)]
+enum NoteDef {
+    Sweep(f32, f32, f32),
+    Tone(f32, f32),
+    Named(String, f32),
+}
+
+fn default_one() -> f32 {
+    1.0
+}
+
+fn default_repeat() -> u32 {
+    1
+}
+
+// The described sounds by name, ready to render.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct SoundBank {
+    sounds: BTreeMap<String, Vec<Voice>>,
+}
+
+impl SoundBank {
+    pub fn voices(&self, name: &str) -> Option<&[Voice]> {
+        self.sounds.get(name).map(Vec::as_slice)
+    }
+
+    // Names of all sounds, in alphabetical order.
+    pub fn names(&self) -> impl Iterator<Item = &str> {
+        self.sounds.keys().map(String::as_str)
+    }
+
+    pub fn render(&self, name: &str) -> Option<Vec<i16>> {
+        self.voices(name).map(render)
+    }
+}
+
+// Parses a sound bank without touching the filesystem.
+pub fn parse_sound_bank(content: &str) -> Result<SoundBank, String> {
+    let def: SoundBankDef = serde_json::from_str(content).map_err(|e| e.to_string())?;
+    let mut waves = HashMap::new();
+    for (name, steps) in def.waves {
+        let wave: Wavetable = steps.as_slice().try_into().map_err(|_| {
+            format!(
+                "wave {:?} has {} steps instead of {}",
+                name,
+                steps.len(),
+                WAVETABLE_LEN
+            )
+        })?;
+        if wave.iter().any(|&step| step > 15) {
+            return Err(format!("wave {:?} has steps above 15", name));
+        }
+        waves.insert(name, wave);
+    }
+
+    let mut sounds = BTreeMap::new();
+    for (name, voice_defs) in def.sounds {
+        let mut voices = Vec::new();
+        for voice in voice_defs {
+            let wave = *waves
+                .get(&voice.wave)
+                .ok_or_else(|| format!("sound {:?}: unknown wave {:?}", name, voice.wave))?;
+            let mut segments = Vec::new();
+            for note in &voice.notes {
+                segments.push(match note {
+                    NoteDef::Sweep(from, to, beats) => {
+                        Segment::sweep(*from, *to, beats * voice.beat)
+                    }
+                    NoteDef::Tone(hz, beats) => Segment::tone(*hz, beats * voice.beat),
+                    NoteDef::Named(note, beats) if note == "-" => Segment::rest(beats * voice.beat),
+                    NoteDef::Named(note, beats) => {
+                        let hz = note_hz(note)
+                            .ok_or_else(|| format!("sound {:?}: unknown note {:?}", name, note))?;
+                        Segment::tone(hz, beats * voice.beat)
+                    }
+                });
+            }
+            voices.push(Voice {
+                wave,
+                volume: voice.volume,
+                gap: voice.gap,
+                segments: segments.repeat(voice.repeat as usize),
+            });
+        }
+        sounds.insert(name, voices);
+    }
+    Ok(SoundBank { sounds })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SOUNDS: &str = include_str!("../resources/sounds.json");
+
+    fn square_voice(segments: Vec<Segment>) -> Voice {
+        let mut wave = [0; WAVETABLE_LEN];
+        wave[..WAVETABLE_LEN / 2].fill(15);
+        Voice {
+            wave,
+            volume: 1.0,
+            gap: 0.0,
+            segments,
+        }
+    }
+
+    fn assert_hz(name: &str, hz: f32) {
+        let actual = note_hz(name).unwrap();
+        assert!(
+            (actual - hz).abs() < 0.01,
+            "{} is {} Hz, not {}",
+            name,
+            actual,
+            hz
+        );
+    }
+
+    #[test]
+    fn notes_are_equal_tempered_around_a4() {
+        assert_eq!(note_hz("A4"), Some(440.0));
+        assert_hz("A5", 880.0);
+        assert_hz("A3", 220.0);
+        assert_hz("C4", 261.63);
+        assert_hz("F#5", 739.99);
+        assert_hz("Bb2", 116.54);
+        assert_eq!(note_hz("C#4"), note_hz("Db4"));
+        assert_eq!(note_hz("B3"), note_hz("Cb4"));
+    }
+
+    #[test]
+    fn bad_note_names_are_rejected() {
+        for name in ["", "H4", "A", "A#", "Ax4", "a4", "A4.5"] {
+            assert_eq!(note_hz(name), None, "{:?}", name);
+        }
+    }
+
+    #[test]
+    fn render_is_as_long_as_the_longest_voice() {
+        let short = square_voice(vec![Segment::tone(440.0, 0.1)]);
+        let long = square_voice(vec![Segment::rest(0.2), Segment::sweep(200.0, 800.0, 0.3)]);
+        assert_eq!(render(std::slice::from_ref(&short)).len(), 2205);
+        assert_eq!(render(&[short, long]).len(), 11025);
+        assert!(render(&[]).is_empty());
+    }
+
+    #[test]
+    fn rests_and_gaps_are_silent() {
+        let mut voice = square_voice(vec![Segment::rest(0.1), Segment::tone(440.0, 0.1)]);
+        voice.gap = 0.05;
+        let samples = render(&[voice]);
+        assert!(samples[..2205].iter().all(|&s| s == 0));
+        assert!(samples[2205..3307].iter().any(|&s| s != 0));
+        assert!(samples[3308..].iter().all(|&s| s == 0));
+    }
+
+    #[test]
+    fn render_is_deterministic() {
+        let voice = square_voice(vec![Segment::sweep(100.0, 1000.0, 0.5)]);
+        assert_eq!(render(std::slice::from_ref(&voice)), render(&[voice]));
+    }
+
+    #[test]
+    fn wav_header_describes_16_bit_mono() {
+        let wav = encode_wav(&[0, 1, -1]);
+        let u16_at = |i: usize| u16::from_le_bytes([wav[i], wav[i + 1]]);
+        let u32_at = |i: usize| u32::from_le_bytes(wav[i..i + 4].try_into().unwrap());
+        assert_eq!(wav.len(), 44 + 6);
+        assert_eq!(&wav[0..4], b"RIFF");
+        assert_eq!(u32_at(4), 36 + 6);
+        assert_eq!(&wav[8..16], b"WAVEfmt ");
+        assert_eq!(u32_at(16), 16);
+        assert_eq!(u16_at(20), 1);
+        assert_eq!(u16_at(22), 1);
+        assert_eq!(u32_at(24), SAMPLE_RATE);
+        assert_eq!(u32_at(28), SAMPLE_RATE * 2);
+        assert_eq!(u16_at(32), 2);
+        assert_eq!(u16_at(34), 16);
+        assert_eq!(&wav[36..40], b"data");
+        assert_eq!(u32_at(40), 6);
+        assert_eq!(&wav[44..], &[0, 0, 1, 0, 0xff, 0xff]);
+    }
+
+    #[test]
+    fn sound_bank_applies_beats_repeats_and_defaults() {
+        let bank = parse_sound_bank(
+            r#"{
+                "waves": { "flat": [8,8,8,8,8,8,8,8,8,8,8,8,8,8,8,8,8,8,8,8,8,8,8,8,8,8,8,8,8,8,8,8] },
+                "sounds": {
+                    "jingle": [{ "wave": "flat", "beat": 0.5, "repeat": 2,
+                                 "notes": [[100, 200, 1], [300, 2], ["A4", 1], ["-", 1]] }]
+                }
+            }"#,
+        )
+        .unwrap();
+        assert_eq!(bank.names().collect::<Vec<_>>(), ["jingle"]);
+        let voices = bank.voices("jingle").unwrap();
+        assert_eq!(voices.len(), 1);
+        assert_eq!(voices[0].volume, 1.0);
+        assert_eq!(voices[0].gap, 0.0);
+        assert_eq!(voices[0].seconds(), 5.0);
+        assert_eq!(
+            voices[0].segments[..4],
+            [
+                Segment::sweep(100.0, 200.0, 0.5),
+                Segment::tone(300.0, 1.0),
+                Segment::tone(440.0, 0.5),
+                Segment::rest(0.5),
+            ]
+        );
+        assert!(bank.render("missing").is_none());
+    }
+
+    #[test]
+    fn invalid_sound_banks_are_rejected() {
+        let bank = |wave: &str, notes: &str| {
+            parse_sound_bank(&format!(
+                r#"{{ "waves": {{ "w": {} }}, "sounds": {{ "s": [{{ "wave": "w", "notes": {} }}] }} }}"#,
+                wave, notes
+            ))
+        };
+        let flat = format!("{:?}", [8; WAVETABLE_LEN]);
+        assert!(bank(&flat, "[]").is_ok());
+        assert!(
+            bank("[1, 2, 3]", "[]")
+                .unwrap_err()
+                .contains("3 steps instead of 32")
+        );
+        let loud = format!("{:?}", [16; WAVETABLE_LEN]);
+        assert!(bank(&loud, "[]").unwrap_err().contains("steps above 15"));
+        assert!(
+            bank(&flat, r#"[["H2", 1]]"#)
+                .unwrap_err()
+                .contains("unknown note")
+        );
+        assert!(bank(&flat, "[[1, 2, 3, 4]]").is_err());
+        assert!(
+            parse_sound_bank(
+                r#"{ "waves": {}, "sounds": { "s": [{ "wave": "w", "notes": [] }] } }"#
+            )
+            .unwrap_err()
+            .contains("unknown wave")
+        );
+        assert!(parse_sound_bank("{}").is_err());
+    }
+
+    // Update these, e.g. from the output of `cargo run --bin synth`, when a change to the
+    // sound bank or the synthesizer is meant to change how the game sounds.
+    #[test]
+    fn game_sounds_render_to_their_golden_hashes() {
+        let golden = [
+            ("death", 36162, 0x0d6923fb35ca651d),
+            ("extra_life", 22050, 0x860bc67992342619),
+            ("eyes", 6615, 0xde5ad66303eedc28),
+            ("frightened", 8820, 0x0bd02520cb0b1db1),
+            ("fruit", 5513, 0x46eee3650e890856),
+            ("intro", 91728, 0xe02d441f1a1e3202),
+            ("siren", 8820, 0x69f8c513d7790931),
+            ("waka_1", 2867, 0xabf2fe44c8a1a3c8),
+            ("waka_2", 2867, 0xbcab88244a77aa04),
+        ];
+        let bank = parse_sound_bank(SOUNDS).unwrap();
+        assert_eq!(
+            bank.names().collect::<Vec<_>>(),
+            golden.map(|(name, _, _)| name)
+        );
+        for (name, len, hash) in golden {
+            let samples = bank.render(name).unwrap();
+            assert_eq!(samples.len(), len, "{}", name);
+            assert_eq!(samples_hash(&samples), hash, "{}", name);
+        }
+    }
+}
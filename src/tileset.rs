@@ -0,0 +1,82 @@
+use crate::Direction;
+use crate::atlas::SpriteAtlas;
+use crate::autotile::{TileScheme, WallKind, WallTile};
+use ggez::graphics::Rect;
+use ggez::{GameError, GameResult};
+
+// The wall sprites of a texture atlas plus the autotiling scheme they were drawn for.
+// The atlas provides `wall_0`..`wall_{N-1}`, with the scheme (`cardinal16` or `blob47`)
+// named by the manifest's `wall_scheme`. Without it the 16-tile scheme is assumed.
+//
+// Two optional extras refine the look:
+// - a full second set `border_wall_0`..`border_wall_{N-1}`, used for walls connected to
+//   the map edge (the arcade's double-line outline);
+// - `door_notch_{north,south,west,east}`, used for walls ending at a ghost house door
+//   on that side.
+// Missing extras fall back to the regular sprites.
+pub struct WallTileset {
+    scheme: TileScheme,
+    tiles: Vec<Rect>,
+    border_tiles: Option<Vec<Rect>>,
+    door_notches: Vec<(Direction, Rect)>,
+}
+
+impl WallTileset {
+    // Collects the wall sprites from an atlas.
+    pub fn from_atlas(atlas: &SpriteAtlas) -> GameResult<WallTileset> {
+        let scheme = match &atlas.manifest().wall_scheme {
+            Some(name) => TileScheme::from_name(name).ok_or_else(|| {
+                GameError::ResourceLoadError(format!("Unknown wall scheme {:?} in atlas", name))
+            })?,
+            None => TileScheme::default(),
+        };
+
+        let tiles = (0..scheme.tile_count())
+            .map(|i| atlas.sprite(&format!("wall_{}", i)))
+            .collect::<GameResult<Vec<_>>>()?;
+
+        let border_tiles = if atlas.contains("border_wall_0") {
+            Some(
+                (0..scheme.tile_count())
+                    .map(|i| atlas.sprite(&format!("border_wall_{}", i)))
+                    .collect::<GameResult<Vec<_>>>()?,
+            )
+        } else {
+            None
+        };
+
+        let door_notches = [
+            (Direction::North, "door_notch_north"),
+            (Direction::South, "door_notch_south"),
+            (Direction::West, "door_notch_west"),
+            (Direction::East, "door_notch_east"),
+        ]
+        .into_iter()
+        .filter_map(|(side, name)| atlas.get(name).map(|rect| (side, rect)))
+        .collect();
+
+        Ok(WallTileset {
+            scheme,
+            tiles,
+            border_tiles,
+            door_notches,
+        })
+    }
+
+    pub fn scheme(&self) -> TileScheme {
+        self.scheme
+    }
+
+    // The atlas UV rect for a tile returned by `WallAutotiler::tile_at`.
+    pub fn rect(&self, tile: WallTile) -> Rect {
+        if let Some(side) = tile.door_side
+            && let Some(&(_, notch)) = self.door_notches.iter().find(|(s, _)| *s == side)
+        {
+            return notch;
+        }
+        match (tile.kind, &self.border_tiles) {
+            (WallKind::Border, Some(border_tiles)) => border_tiles[tile.index],
+            _ => self.tiles[tile.index],
+        }
+    }
+}
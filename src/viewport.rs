@@ -0,0 +1,100 @@
+use ggez::glam::Vec2;
+use ggez::graphics::{Canvas, Rect};
+use ggez::{Context, GameResult};
+
+// How the logical canvas is scaled up to fill the window.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ScaleMode {
+    Integer, // Whole multiples only, so every logical pixel is the same size on screen.
+    Fit,     // As large as the window allows, at the cost of uneven pixels.
+}
+
+impl ScaleMode {
+    pub fn toggled(self) -> ScaleMode {
+        match self {
+            ScaleMode::Integer => ScaleMode::Fit,
+            ScaleMode::Fit => ScaleMode::Integer,
+        }
+    }
+}
+
+// Maps a fixed-size logical canvas (224x288 for the game, 448x320 for the editor) into a
+// window of any size: scaled by `mode`, centered, with black bars filling the rest.
+// Window sizes and positions are in physical pixels, as reported by
+// `ctx.gfx.drawable_size()` and `ctx.mouse.position()`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Viewport {
+    pub logical_size: Vec2,
+    pub mode: ScaleMode,
+}
+
+impl Viewport {
+    pub fn new(width: f32, height: f32, mode: ScaleMode) -> Viewport {
+        Viewport {
+            logical_size: Vec2::new(width, height),
+            mode,
+        }
+    }
+
+    // Window pixels per logical pixel. Integer scaling falls back to fitting when the
+    // window is smaller than the logical canvas.
+    pub fn scale(&self, window_size: Vec2) -> f32 {
+        let fit = (window_size / self.logical_size).min_element();
+        match self.mode {
+            ScaleMode::Integer if fit >= 1.0 => fit.floor(),
+            _ => fit.max(f32::EPSILON),
+        }
+    }
+
+    // `scale` for the window as it is now.
+    pub fn window_scale(&self, ctx: &Context) -> f32 {
+        self.scale(Vec2::from(ctx.gfx.drawable_size()))
+    }
+
+    // The area of the window the logical canvas covers, starting on a whole pixel.
+    pub fn area(&self, window_size: Vec2) -> Rect {
+        let size = self.logical_size * self.scale(window_size);
+        let origin = ((window_size - size) / 2.0).floor();
+        Rect::new(origin.x, origin.y, size.x, size.y)
+    }
+
+    // Converts a window position, e.g. the mouse, to logical canvas coordinates.
+    // Positions over the black bars map outside `0..logical_size`.
+    pub fn window_to_logical(&self, window_size: Vec2, point: Vec2) -> Vec2 {
+        let area = self.area(window_size);
+        (point - Vec2::new(area.x, area.y)) / self.scale(window_size)
+    }
+
+    // Screen coordinates for a canvas covering the whole window, such that logical
+    // (0, 0) lands on the top-left corner of `area`.
+    pub fn screen_coordinates(&self, window_size: Vec2) -> Rect {
+        let area = self.area(window_size);
+        let scale = self.scale(window_size);
+        Rect::new(
+            -area.x / scale,
+            -area.y / scale,
+            window_size.x / scale,
+            window_size.y / scale,
+        )
+    }
+
+    // Sets up a frame canvas for this viewport, clipping drawing to the logical area so
+    // the bars stay black.
+    pub fn apply(&self, ctx: &Context, canvas: &mut Canvas) -> GameResult {
+        // The frame image, not the window, so the scissor rect always fits the canvas.
+        let frame = ctx.gfx.frame();
+        let window_size = Vec2::new(frame.width() as f32, frame.height() as f32);
+        canvas.set_screen_coordinates(self.screen_coordinates(window_size));
+        let area = self.area(window_size);
+        if area.w >= 1.0 && area.h >= 1.0 {
+            canvas.set_scissor_rect(area)?;
+        }
+        Ok(())
+    }
+
+    // The current mouse position in logical canvas coordinates.
+    pub fn mouse_position(&self, ctx: &Context) -> Vec2 {
+        let window_size = Vec2::from(ctx.gfx.drawable_size());
+        self.window_to_logical(window_size, Vec2::from(ctx.mouse.position()))
+    }
+}
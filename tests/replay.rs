@@ -0,0 +1,74 @@
+// Plays a recorded game back to catch gameplay changes that break old replays. The
+// fixture is a bot playing level 1 from seed 2024 until its last life is lost; if a rule
+// change is intended, record a new one and update the expected results below.
+use rust_pack::replay::{parse_replay, play, save_replay_to_string};
+use rust_pack::*;
+
+const LEVEL: &str = include_str!("../resources/levels/level1.txt");
+const BOT_GAME: &str = include_str!("fixtures/bot_game.json");
+const DEMO: &str = include_str!("../resources/demo.json");
+
+#[test]
+fn bot_game_plays_back_to_the_recorded_result() {
+    let mut replay = parse_replay(BOT_GAME).unwrap();
+    let expected = replay.clone();
+    let mut sim = replay.start(load_level_from_string(LEVEL)).unwrap();
+
+    let mut mismatches = Vec::new();
+    let length = replay.length;
+    let deaths = play(&mut sim, &mut replay, length, |sim| {
+        let tick = sim.tick_count();
+        if let Some(hash) = expected.expected_hash(tick)
+            && hash != sim.state_hash()
+        {
+            mismatches.push(tick);
+        }
+    });
+
+    assert_eq!(
+        mismatches,
+        Vec::<u64>::new(),
+        "state hashes diverge at these ticks"
+    );
+    assert_eq!(sim.tick_count(), 5638);
+    assert_eq!(deaths, 3);
+    assert_eq!(sim.scoreboard.lives, 0);
+    assert_eq!(sim.scoreboard.score(), 3670);
+    assert_eq!(sim.state_hash(), 0xb20b84e79c303231);
+}
+
+#[test]
+fn bot_game_fixture_round_trips() {
+    let replay = parse_replay(BOT_GAME).unwrap();
+    assert_eq!(save_replay_to_string(&replay), BOT_GAME.trim_end());
+}
+
+#[test]
+fn replay_refuses_a_different_level() {
+    let replay = parse_replay(BOT_GAME).unwrap();
+    let mut level = load_level_from_string(LEVEL);
+    level.set(1, 1, 0);
+    assert!(replay.start(level).is_err());
+}
+
+#[test]
+fn demo_plays_back_until_the_player_is_caught() {
+    let mut demo = parse_replay(DEMO).unwrap();
+    let expected = demo.clone();
+    let mut sim = demo.start(load_level_from_string(LEVEL)).unwrap();
+
+    // Stepped like the attract mode does, which ends at the first death.
+    while !sim.is_player_caught() {
+        sim.step(&mut demo);
+        let tick = sim.tick_count();
+        if let Some(hash) = expected.expected_hash(tick) {
+            assert_eq!(
+                sim.state_hash(),
+                hash,
+                "state hash diverges at tick {}",
+                tick
+            );
+        }
+    }
+    assert_eq!(sim.tick_count(), demo.length);
+}